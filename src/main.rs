@@ -1,17 +1,48 @@
+// explicit returns and `field: field` initializers are used throughout the code
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 #[allow(non_camel_case_types)]
 #[warn(non_snake_case)]
 #[allow(non_upper_case_globals)]
-
 mod raytracer;
+mod scene;
+#[allow(clippy::vec_init_then_push)]
 mod setup;
 mod utils {
-    pub mod sphere;
+    pub mod background;
+    pub mod bvh;
+    pub mod camera;
+    pub mod filter;
     pub mod light;
     pub mod material;
+    pub mod mesh;
+    pub mod plane;
+    pub mod procedural;
+    pub mod sampler;
+    pub mod sampling;
+    pub mod shape;
+    pub mod sky;
+    pub mod sphere;
+    pub mod texture;
+    pub mod tonemap;
+    pub mod triangle;
     pub mod vec3;
 }
 
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::parser::ValueSource;
 use clap::{value_parser, Arg, ArgMatches, Command};
+use raytracer::{Integrator, OutputFormat, RenderSettings, StereoMode};
+use std::sync::Arc;
+use utils::background::{Background, BackgroundMode, Backplate, EnvironmentMap};
+use utils::camera::{Camera, Projection};
+use utils::filter::{Filter, FilterKind};
+use utils::light::Light;
+use utils::sampler::{AdaptiveSampling, SamplePattern, Sampler};
+use utils::shape::Shape;
+use utils::sky::{self, Sky};
+use utils::texture::{ImageTexture, Texture};
+use utils::tonemap::{Gamma, ToneMapper, ToneMapping};
 
 fn main() {
     let matches: ArgMatches = cli().get_matches();
@@ -59,10 +90,90 @@ fn cli() -> Command {
                 .short('a')
                 .long("anti-aliasing")
                 .default_value("2")
-                .value_parser(value_parser!(u32))
+                .value_parser(value_parser!(u32).range(1..))
                 .help("Set scale of anti aliasing (number of rays per pixel = <argument> ^ 2)")
                 .global(true),
         )
+        .arg(
+            Arg::new("sampler")
+                .long("sampler")
+                .default_value("grid")
+                .value_parser(
+                    PossibleValuesParser::new(["grid", "jittered", "random", "halton", "sobol", "rotated-grid"])
+                        .map(|s| match s.as_str() {
+                            "jittered" => SamplePattern::Jittered,
+                            "random" => SamplePattern::Random,
+                            "halton" => SamplePattern::Halton,
+                            "sobol" => SamplePattern::Sobol,
+                            "rotated-grid" => SamplePattern::RotatedGrid,
+                            _ => SamplePattern::Grid,
+                        }),
+                )
+                .help("Distribution of the anti aliasing samples inside a pixel")
+                .global(true),
+        )
+        .arg(
+            Arg::new("samples")
+                .long("samples")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Number of rays per pixel (overrides --anti-aliasing)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("adaptive")
+                .long("adaptive")
+                .action(clap::ArgAction::SetTrue)
+                .help("Only spend more than --min-samples rays on pixels with a high variance")
+                .global(true),
+        )
+        .arg(
+            Arg::new("min_samples")
+                .long("min-samples")
+                .default_value("4")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Number of rays per pixel before adaptive sampling may stop")
+                .global(true),
+        )
+        .arg(
+            Arg::new("adaptive_threshold")
+                .long("adaptive-threshold")
+                .default_value("0.01")
+                .value_parser(value_parser!(f32))
+                .help("Adaptive sampling stops once the standard error of the pixel luminance is below this value")
+                .global(true),
+        )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .default_value("box")
+                .value_parser(
+                    PossibleValuesParser::new(["box", "tent", "gaussian", "mitchell", "lanczos"])
+                        .map(|s| match s.as_str() {
+                            "tent" => FilterKind::Tent,
+                            "gaussian" => FilterKind::Gaussian,
+                            "mitchell" => FilterKind::Mitchell,
+                            "lanczos" => FilterKind::Lanczos,
+                            _ => FilterKind::Box,
+                        }),
+                )
+                .help("Reconstruction filter weighting the samples of a pixel and its neighbours")
+                .global(true),
+        )
+        .arg(
+            Arg::new("filter_radius")
+                .long("filter-radius")
+                .value_parser(value_parser!(f32))
+                .help("Radius of the reconstruction filter in pixels (default depends on the filter)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .default_value("0")
+                .value_parser(value_parser!(u64))
+                .help("Seed for the random numbers used by the samplers and the path integrator")
+                .global(true),
+        )
         .arg(
             Arg::new("occlusion_offset")
                 .long("occlusion-offset")
@@ -74,9 +185,9 @@ fn cli() -> Command {
         .arg(
             Arg::new("fov")
                 .long("fov")
-                .default_value("1.0")
+                .default_value("57.29578")
                 .value_parser(value_parser!(f32))
-                .help("Field of view")
+                .help("Vertical field of view in degrees")
                 .global(true),
         )
         .arg(
@@ -84,8 +195,16 @@ fn cli() -> Command {
                 .short('p')
                 .long("preset")
                 .default_value("1")
-                .value_parser(value_parser!(u32).range(1..5))
-                .help("Select preset [1-4]")
+                .value_parser(value_parser!(u32).range(1..7))
+                .help("Select preset [1-6] (preset 5 is a benchmark scene with several thousand spheres, preset 6 shows procedural textures)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("scene")
+                .short('s')
+                .long("scene")
+                .value_name("FILE")
+                .help("Load the scene from a TOML scene-description file instead of a preset")
                 .global(true),
         )
         .arg(
@@ -93,10 +212,353 @@ fn cli() -> Command {
                 .long("look-at-pos")
                 .default_value("0,-4,-20")
                 .value_delimiter(',')
-                .value_parser(value_parser!(i32))
+                .value_parser(value_parser!(f32))
                 .allow_hyphen_values(true)
                 .help("Set position of point to look at")
         )
+        .arg(
+            Arg::new("up")
+                .long("up")
+                .default_value("0,1,0")
+                .value_delimiter(',')
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Up direction of the camera")
+                .global(true),
+        )
+        .arg(
+            Arg::new("aperture")
+                .long("aperture")
+                .default_value("0.0")
+                .value_parser(value_parser!(f32))
+                .help("Radius of the camera lens, values greater than 0 enable depth of field")
+                .global(true),
+        )
+        .arg(
+            Arg::new("focus_distance")
+                .long("focus-distance")
+                .value_parser(value_parser!(f32))
+                .help("Distance of the plane in focus [default: distance to the look-at position]")
+                .global(true),
+        )
+        .arg(
+            Arg::new("roll")
+                .long("roll")
+                .default_value("0.0")
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Rotation of the camera around the viewing direction in degrees")
+                .global(true),
+        )
+        .arg(
+            Arg::new("projection")
+                .long("projection")
+                .default_value("perspective")
+                .value_parser(
+                    PossibleValuesParser::new(["perspective", "orthographic", "equirectangular", "fisheye"])
+                        .map(|s| match s.as_str() {
+                            "orthographic" => Projection::Orthographic,
+                            "equirectangular" => Projection::Equirectangular,
+                            "fisheye" => Projection::Fisheye,
+                            _ => Projection::Perspective,
+                        }),
+                )
+                .help("Projection of the camera")
+                .global(true),
+        )
+        .arg(
+            Arg::new("view_width")
+                .long("view-width")
+                .default_value("20.0")
+                .value_parser(value_parser!(f32))
+                .help("Width of the visible area of the orthographic projection in world units")
+                .global(true),
+        )
+        .arg(
+            Arg::new("fisheye_fov")
+                .long("fisheye-fov")
+                .default_value("180.0")
+                .value_parser(value_parser!(f32))
+                .help("Field of view of the fisheye projection in degrees")
+                .global(true),
+        )
+        .arg(
+            Arg::new("background")
+                .long("background")
+                .default_value("gradient")
+                .value_parser(PossibleValuesParser::new(["gradient", "solid"]).map(|s| {
+                    match s.as_str() {
+                        "solid" => BackgroundMode::Solid,
+                        _ => BackgroundMode::Gradient,
+                    }
+                }))
+                .help("Background seen by rays that do not hit any object")
+                .global(true),
+        )
+        .arg(
+            Arg::new("background_color")
+                .long("background-color")
+                .default_value("53,108,160")
+                .value_delimiter(',')
+                .value_parser(value_parser!(u32).range(0..256))
+                .help("Color of the solid background, the gradient defaults to it at 80 % brightness at the top and black at the bottom")
+                .global(true),
+        )
+        .arg(
+            Arg::new("background_top")
+                .long("background-top")
+                .value_delimiter(',')
+                .value_parser(value_parser!(u32).range(0..256))
+                .help("Color of the gradient straight up")
+                .global(true),
+        )
+        .arg(
+            Arg::new("background_bottom")
+                .long("background-bottom")
+                .value_delimiter(',')
+                .value_parser(value_parser!(u32).range(0..256))
+                .help("Color of the gradient straight down")
+                .global(true),
+        )
+        .arg(
+            Arg::new("backplate")
+                .long("backplate")
+                .value_name("FILE")
+                .help("Image shown behind the objects, scaled to the size of the rendered image")
+                .global(true),
+        )
+        .arg(
+            Arg::new("environment")
+                .long("environment")
+                .value_name("FILE")
+                .help("Equirectangular environment map (hdr, exr or png) used as background and for lighting the scene")
+                .global(true),
+        )
+        .arg(
+            Arg::new("environment_rotation")
+                .long("environment-rotation")
+                .default_value("0.0")
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Rotation of the environment map around the y-axis in degrees")
+                .global(true),
+        )
+        .arg(
+            Arg::new("environment_intensity")
+                .long("environment-intensity")
+                .default_value("1.0")
+                .value_parser(value_parser!(f32))
+                .help("Factor the radiance of the environment map is multiplied with")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sky")
+                .long("sky")
+                .action(clap::ArgAction::SetTrue)
+                .help("Use an analytic daylight sky (Preetham) as background and add a matching sun light")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sun_elevation")
+                .long("sun-elevation")
+                .default_value("45.0")
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Angle of the sun above the horizon in degrees")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sun_azimuth")
+                .long("sun-azimuth")
+                .default_value("0.0")
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Direction of the sun in degrees, measured from -z towards +x")
+                .global(true),
+        )
+        .arg(
+            Arg::new("turbidity")
+                .long("turbidity")
+                .default_value("3.0")
+                .value_parser(value_parser!(f32))
+                .help("Haziness of the atmosphere, from 2 (very clear) to 10 (hazy)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sun_intensity")
+                .long("sun-intensity")
+                .default_value("1.0")
+                .value_parser(value_parser!(f32))
+                .help("Intensity of the sun light (before the attenuation by the atmosphere)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("sky_intensity")
+                .long("sky-intensity")
+                .default_value("1.0")
+                .value_parser(value_parser!(f32))
+                .help("Factor the radiance of the sky is multiplied with")
+                .global(true),
+        )
+        .arg(
+            Arg::new("no_floor")
+                .long("no-floor")
+                .action(clap::ArgAction::SetTrue)
+                .help("Disable the floor")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_center")
+                .long("floor-center")
+                .default_value("0,-4,-30")
+                .value_delimiter(',')
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Center of the floor")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_size")
+                .long("floor-size")
+                .default_value("20,40")
+                .value_delimiter(',')
+                .value_parser(value_parser!(f32))
+                .help("Width and depth of the floor")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_normal")
+                .long("floor-normal")
+                .default_value("0,1,0")
+                .value_delimiter(',')
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Normal (orientation) of the floor")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_color")
+                .long("floor-color")
+                .default_value("230,102,30")
+                .value_delimiter(',')
+                .value_parser(value_parser!(u32).range(0..256))
+                .help("Color of the floor")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_checker_color")
+                .long("floor-checker-color")
+                .value_delimiter(',')
+                .value_parser(value_parser!(u32).range(0..256))
+                .help("Second color of the floor, enables a checkerboard pattern")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_tile_size")
+                .long("floor-tile-size")
+                .default_value("2.0")
+                .value_parser(value_parser!(f32))
+                .help("Size of the tiles of the checkerboard pattern")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_texture")
+                .long("floor-texture")
+                .value_name("FILE")
+                .help("Image replacing the color of the floor")
+                .global(true),
+        )
+        .arg(
+            Arg::new("floor_texture_scale")
+                .long("floor-texture-scale")
+                .default_value("1,1")
+                .value_delimiter(',')
+                .value_parser(value_parser!(f32))
+                .help("Number of repetitions of the floor texture along the width and the depth")
+                .global(true),
+        )
+        .arg(
+            Arg::new("accelerator")
+                .long("accelerator")
+                .default_value("bvh")
+                .value_parser(["bvh", "linear"])
+                .help("Acceleration structure used for finding ray-object intersections")
+                .global(true),
+        )
+        .arg(
+            Arg::new("integrator")
+                .long("integrator")
+                .default_value("whitted")
+                .value_parser(PossibleValuesParser::new(["whitted", "path"]).map(|s| {
+                    match s.as_str() {
+                        "path" => Integrator::Path,
+                        _ => Integrator::Whitted,
+                    }
+                }))
+                .help("Rendering algorithm: Whitted-style ray tracing or Monte Carlo path tracing")
+                .global(true),
+        )
+        .arg(
+            Arg::new("path_samples")
+                .long("spp")
+                .default_value("16")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Number of paths traced per anti-aliasing sample (path integrator only)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("shadow_samples")
+                .long("shadow-samples")
+                .default_value("16")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Number of shadow rays per area light and shading point")
+                .global(true),
+        )
+        .arg(
+            Arg::new("tone_mapping")
+                .long("tone-mapping")
+                .default_value("clamp")
+                .value_parser(PossibleValuesParser::new(["clamp", "reinhard", "aces"]).map(|s| {
+                    match s.as_str() {
+                        "reinhard" => ToneMapping::Reinhard,
+                        "aces" => ToneMapping::Aces,
+                        _ => ToneMapping::Clamp,
+                    }
+                }))
+                .help("Tone mapping operator applied before storing colors with 8 bit (png, gif)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("exposure")
+                .long("exposure")
+                .default_value("0.0")
+                .value_parser(value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Exposure correction in stops applied before tone mapping")
+                .global(true),
+        )
+        .arg(
+            Arg::new("gamma")
+                .long("gamma")
+                .default_value("linear")
+                .value_parser(PossibleValuesParser::new(["linear", "srgb"]).map(|s| {
+                    match s.as_str() {
+                        "srgb" => Gamma::Srgb,
+                        _ => Gamma::Linear,
+                    }
+                }))
+                .help("Encoding of tone mapped colors: linear or sRGB gamma")
+                .global(true),
+        )
+        .arg(
+            Arg::new("threads")
+                .short('t')
+                .long("threads")
+                .default_value("0")
+                .value_parser(value_parser!(usize))
+                .help("Number of worker threads used for rendering (0 = one per CPU core)")
+                .global(true),
+        )
         .arg(
             Arg::new("versionize")
                 .long("versionize")
@@ -112,9 +574,53 @@ fn cli() -> Command {
                         .long("camera-pos")
                         .default_value("0,0,0")
                         .value_delimiter(',')
-                        .value_parser(value_parser!(i32))
+                        .value_parser(value_parser!(f32))
                         .allow_hyphen_values(true)
                         .help("Set position of camera"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .default_value("png")
+                        .value_parser(PossibleValuesParser::new(["png", "exr", "hdr"]).map(|s| {
+                            match s.as_str() {
+                                "exr" => OutputFormat::Exr,
+                                "hdr" => OutputFormat::Hdr,
+                                _ => OutputFormat::Png,
+                            }
+                        }))
+                        .help("File format of the image (exr and hdr store the unclamped linear colors)"),
+                )
+                .arg(
+                    Arg::new("sample_map")
+                        .long("sample-map")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Additionally save an image of the number of rays spent per pixel"),
+                )
+                .arg(
+                    Arg::new("stereo")
+                        .long("stereo")
+                        .value_parser(PossibleValuesParser::new(["sbs", "tb", "anaglyph"]).map(|s| {
+                            match s.as_str() {
+                                "tb" => StereoMode::TopBottom,
+                                "anaglyph" => StereoMode::Anaglyph,
+                                _ => StereoMode::SideBySide,
+                            }
+                        }))
+                        .help("Render a stereo image: side by side, top-bottom or red/cyan anaglyph"),
+                )
+                .arg(
+                    Arg::new("eye_separation")
+                        .long("eye-separation")
+                        .default_value("0.6")
+                        .value_parser(value_parser!(f32))
+                        .help("Distance between the left and the right eye of the stereo image"),
+                )
+                .arg(
+                    Arg::new("convergence")
+                        .long("convergence")
+                        .value_parser(value_parser!(f32))
+                        .help("Distance of the plane appearing on the screen in the stereo image [default: distance to the look-at position]"),
                 ),
         )
         .subcommand(
@@ -124,7 +630,8 @@ fn cli() -> Command {
                 Arg::new("y_level")
                     .long("y-level")
                     .default_value("20")
-                    .value_parser(value_parser!(i32))
+                    .value_parser(value_parser!(f32))
+                    .allow_hyphen_values(true)
                     .help("y-level at which the camera is")
             )
             .arg(
@@ -141,70 +648,537 @@ fn cli() -> Command {
                     .value_parser(value_parser!(u32))
                     .help("num_of_images")
             )
+            .arg(
+                Arg::new("aperture_end")
+                    .long("aperture-end")
+                    .value_parser(value_parser!(f32))
+                    .help("Aperture in the last image, changes linearly from --aperture [default: --aperture]")
+            )
+            .arg(
+                Arg::new("focus_distance_end")
+                    .long("focus-distance-end")
+                    .value_parser(value_parser!(f32))
+                    .help("Focus distance in the last image, changes linearly from --focus-distance [default: --focus-distance]")
+            )
         )
 }
 
+fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// Returns the value of the argument `id`, unless it was not given explicitly on the command
+/// line and the scene file provides a value for it.
+fn arg_or_scene<T: Clone + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    scene_value: Option<T>,
+) -> T {
+    match scene_value {
+        Some(value) if !is_explicit(matches, id) => value,
+        _ => matches.get_one::<T>(id).unwrap().clone(),
+    }
+}
+
+/// Same as `arg_or_scene` for arguments consisting of two comma separated values
+fn pair_arg_or_scene<T: Copy + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    scene_value: Option<(T, T)>,
+) -> (T, T) {
+    match scene_value {
+        Some(value) if !is_explicit(matches, id) => value,
+        _ => {
+            let values: Vec<T> = matches.get_many::<T>(id).unwrap().copied().collect();
+            (values[0], values[1])
+        }
+    }
+}
+
+/// Same as `arg_or_scene` for arguments consisting of three comma separated values
+fn triple_arg_or_scene<T: Copy + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    scene_value: Option<(T, T, T)>,
+) -> (T, T, T) {
+    match scene_value {
+        Some(value) if !is_explicit(matches, id) => value,
+        _ => {
+            let values: Vec<T> = matches.get_many::<T>(id).unwrap().copied().collect();
+            (values[0], values[1], values[2])
+        }
+    }
+}
+
+fn to_vec3(v: (f32, f32, f32)) -> utils::vec3::vec3 {
+    utils::vec3::vec3 {
+        x: v.0,
+        y: v.1,
+        z: v.2,
+    }
+}
+
 fn start_raytracer(matches: ArgMatches) {
+    let scene = match matches.get_one::<String>("scene") {
+        Some(path) => match scene::Scene::from_file(path) {
+            Ok(scene) => Some(scene),
+            Err(e) => {
+                eprintln!("Failed to load scene: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     let width = *matches.get_one::<u32>("width").unwrap();
     let height = *matches.get_one::<u32>("height").unwrap();
     let output_path = matches.get_one::<String>("output_path").unwrap();
-    let max_depth = *matches.get_one::<u32>("max_depth").unwrap();
-    let anti_aliasing = *matches.get_one::<u32>("anti_aliasing").unwrap();
-    let occlusion_offset = *matches.get_one::<f32>("occlusion_offset").unwrap();
-    let fov = *matches.get_one::<f32>("fov").unwrap();
-    let preset = *matches.get_one::<u32>("preset").unwrap();
-    let (spheres, lights) = match preset {
-        1 => setup::get_spheres_lights_1(),
-        2 => setup::get_spheres_lights_2(),
-        3 => setup::get_spheres_lights_3(),
-        4 => setup::get_spheres_lights_4(),
-        _ => setup::get_spheres_lights_1(),
+    let max_depth = arg_or_scene(
+        &matches,
+        "max_depth",
+        scene.as_ref().and_then(|s| s.max_depth),
+    );
+    let anti_aliasing = arg_or_scene(
+        &matches,
+        "anti_aliasing",
+        scene.as_ref().and_then(|s| s.anti_aliasing),
+    );
+    let samples = match matches.get_one::<u32>("samples") {
+        Some(samples) => *samples,
+        None => match scene.as_ref().and_then(|s| s.samples) {
+            Some(samples) if !is_explicit(&matches, "anti_aliasing") => samples,
+            _ => anti_aliasing * anti_aliasing,
+        },
     };
-    let look_at_pos_tmp: Vec<Vec<&i32>> = matches
-        .get_occurrences("look_at_pos")
-        .unwrap()
-        .map(Iterator::collect)
-        .collect();
-    let look_at_point: (f32, f32, f32) = (
-        *look_at_pos_tmp[0][0] as f32,
-        *look_at_pos_tmp[0][1] as f32,
-        *look_at_pos_tmp[0][2] as f32,
+    let sampler = Sampler::new(
+        arg_or_scene(&matches, "sampler", scene.as_ref().and_then(|s| s.sampler)),
+        samples,
+    );
+    let adaptive = if matches.get_flag("adaptive")
+        || scene.as_ref().and_then(|s| s.adaptive).unwrap_or(false)
+    {
+        Some(AdaptiveSampling::new(
+            arg_or_scene(
+                &matches,
+                "min_samples",
+                scene.as_ref().and_then(|s| s.min_samples),
+            ),
+            arg_or_scene(
+                &matches,
+                "adaptive_threshold",
+                scene.as_ref().and_then(|s| s.adaptive_threshold),
+            ),
+        ))
+    } else {
+        None
+    };
+    let seed = arg_or_scene(&matches, "seed", scene.as_ref().and_then(|s| s.seed));
+    let filter_kind = arg_or_scene(&matches, "filter", scene.as_ref().and_then(|s| s.filter));
+    let filter_radius = match matches.get_one::<f32>("filter_radius") {
+        Some(radius) => *radius,
+        None => scene
+            .as_ref()
+            .and_then(|s| s.filter_radius)
+            .unwrap_or(filter_kind.default_radius()),
+    };
+    let filter = Filter::new(filter_kind, filter_radius);
+    let occlusion_offset = arg_or_scene(
+        &matches,
+        "occlusion_offset",
+        scene.as_ref().and_then(|s| s.occlusion_offset),
+    );
+    let fov = arg_or_scene(&matches, "fov", scene.as_ref().and_then(|s| s.fov));
+    let preset = *matches.get_one::<u32>("preset").unwrap();
+    let look_at_point = triple_arg_or_scene(
+        &matches,
+        "look_at_pos",
+        scene.as_ref().and_then(|s| s.look_at_pos),
     );
     let versionize = matches.get_flag("versionize");
+    let threads = *matches.get_one::<usize>("threads").unwrap();
+    let use_bvh = matches.get_one::<String>("accelerator").unwrap() == "bvh";
+    let integrator = arg_or_scene(
+        &matches,
+        "integrator",
+        scene.as_ref().and_then(|s| s.integrator),
+    );
+    let path_samples = arg_or_scene(
+        &matches,
+        "path_samples",
+        scene.as_ref().and_then(|s| s.path_samples),
+    );
+    let shadow_samples = arg_or_scene(
+        &matches,
+        "shadow_samples",
+        scene.as_ref().and_then(|s| s.shadow_samples),
+    );
+
+    let tone_mapper = ToneMapper::new(
+        arg_or_scene(
+            &matches,
+            "tone_mapping",
+            scene.as_ref().and_then(|s| s.tone_mapping),
+        ),
+        arg_or_scene(
+            &matches,
+            "exposure",
+            scene.as_ref().and_then(|s| s.exposure),
+        ),
+        arg_or_scene(&matches, "gamma", scene.as_ref().and_then(|s| s.gamma)),
+    );
 
-    let mut tracer = raytracer::Raytracer::new(
-        (width, height),
-        (10.0, 40.0),
-        (53, 108, 160),
-        (230, 102, 30),
-        -4.0,
-        max_depth,
-        occlusion_offset,
-        anti_aliasing,
-        fov,
-        spheres,
-        lights,
+    let preset_floor = setup::get_floor(preset);
+    let floor_checker_color = match matches.get_many::<u32>("floor_checker_color") {
+        Some(color) => {
+            let color: Vec<u32> = color.copied().collect();
+            Some((color[0], color[1], color[2]))
+        }
+        None => scene
+            .as_ref()
+            .and_then(|s| s.floor_checker_color)
+            .or(preset_floor.checker.map(|(color, _)| color)),
+    };
+    let floor_tile_size = arg_or_scene(
+        &matches,
+        "floor_tile_size",
+        scene
+            .as_ref()
+            .and_then(|s| s.floor_tile_size)
+            .or(preset_floor.checker.map(|(_, tile_size)| tile_size)),
+    );
+    let floor_texture_scale = pair_arg_or_scene(
+        &matches,
+        "floor_texture_scale",
+        scene.as_ref().and_then(|s| s.floor_texture_scale),
+    );
+    let floor_texture = matches
+        .get_one::<String>("floor_texture")
+        .cloned()
+        .or(scene.as_ref().and_then(|s| s.floor_texture.clone()))
+        .map(
+            |path| match ImageTexture::load(std::path::Path::new(&path), true) {
+                Ok(texture) => Arc::new(Texture::Image(texture.with_scale(floor_texture_scale))),
+                Err(e) => {
+                    eprintln!("Failed to load floor texture '{}': {}", path, e);
+                    std::process::exit(1);
+                }
+            },
+        )
+        .or(scene.as_ref().and_then(|s| s.floor_pattern.clone()))
+        .or(preset_floor.texture.clone());
+    let floor = setup::Floor {
+        enabled: !matches.get_flag("no_floor")
+            && scene
+                .as_ref()
+                .and_then(|s| s.floor_enabled)
+                .unwrap_or(preset_floor.enabled),
+        center: triple_arg_or_scene(
+            &matches,
+            "floor_center",
+            scene
+                .as_ref()
+                .and_then(|s| s.floor_center)
+                .or(Some(preset_floor.center)),
+        ),
+        size: pair_arg_or_scene(
+            &matches,
+            "floor_size",
+            scene
+                .as_ref()
+                .and_then(|s| s.floor_size)
+                .or(Some(preset_floor.size)),
+        ),
+        normal: triple_arg_or_scene(
+            &matches,
+            "floor_normal",
+            scene
+                .as_ref()
+                .and_then(|s| s.floor_normal)
+                .or(Some(preset_floor.normal)),
+        ),
+        color: triple_arg_or_scene(
+            &matches,
+            "floor_color",
+            scene
+                .as_ref()
+                .and_then(|s| s.floor_color)
+                .or(Some(preset_floor.color)),
+        ),
+        checker: floor_checker_color.map(|color| (color, floor_tile_size)),
+        texture: floor_texture,
+    };
+    let background_mode = arg_or_scene(
+        &matches,
+        "background",
+        scene.as_ref().and_then(|s| s.background_mode),
+    );
+    let background_color = triple_arg_or_scene(
+        &matches,
+        "background_color",
+        scene.as_ref().and_then(|s| s.background_color),
+    );
+    let background_color = to_vec3((
+        background_color.0 as f32,
+        background_color.1 as f32,
+        background_color.2 as f32,
+    )) / 255.0;
+    let gradient_color = |id: &str, scene_value: Option<(u32, u32, u32)>| {
+        let color = match matches.get_many::<u32>(id) {
+            Some(color) => {
+                let color: Vec<u32> = color.copied().collect();
+                Some((color[0], color[1], color[2]))
+            }
+            None => scene_value,
+        };
+        color.map(|c| to_vec3((c.0 as f32, c.1 as f32, c.2 as f32)) / 255.0)
+    };
+    let background_top = gradient_color(
+        "background_top",
+        scene.as_ref().and_then(|s| s.background_top),
+    )
+    .unwrap_or(background_color * 0.8);
+    let background_bottom = gradient_color(
+        "background_bottom",
+        scene.as_ref().and_then(|s| s.background_bottom),
+    )
+    .unwrap_or(to_vec3((0.0, 0.0, 0.0)));
+    let backplate = matches
+        .get_one::<String>("backplate")
+        .cloned()
+        .or(scene.as_ref().and_then(|s| s.backplate.clone()))
+        .map(|path| {
+            match Backplate::load(
+                std::path::Path::new(&path),
+                tone_mapper.gamma == Gamma::Srgb,
+            ) {
+                Ok(backplate) => backplate,
+                Err(e) => {
+                    eprintln!("Failed to load backplate '{}': {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        });
+    let environment = matches
+        .get_one::<String>("environment")
+        .cloned()
+        .or(scene.as_ref().and_then(|s| s.environment.clone()));
+    let sky_enabled =
+        matches.get_flag("sky") || scene.as_ref().and_then(|s| s.sky).unwrap_or(false);
+    let mut sun_light = None;
+    let background = match environment {
+        Some(path) => {
+            let rotation = arg_or_scene(
+                &matches,
+                "environment_rotation",
+                scene.as_ref().and_then(|s| s.environment_rotation),
+            );
+            let intensity = arg_or_scene(
+                &matches,
+                "environment_intensity",
+                scene.as_ref().and_then(|s| s.environment_intensity),
+            );
+            match EnvironmentMap::load(std::path::Path::new(&path), rotation, intensity) {
+                Ok(map) => Background::Environment(map),
+                Err(e) => {
+                    eprintln!("Failed to load environment map '{}': {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None if sky_enabled => {
+            let elevation = arg_or_scene(
+                &matches,
+                "sun_elevation",
+                scene.as_ref().and_then(|s| s.sun_elevation),
+            );
+            let azimuth = arg_or_scene(
+                &matches,
+                "sun_azimuth",
+                scene.as_ref().and_then(|s| s.sun_azimuth),
+            );
+            let sky = Sky::new(
+                sky::sun_direction(elevation, azimuth),
+                arg_or_scene(
+                    &matches,
+                    "turbidity",
+                    scene.as_ref().and_then(|s| s.turbidity),
+                ),
+                arg_or_scene(
+                    &matches,
+                    "sky_intensity",
+                    scene.as_ref().and_then(|s| s.sky_intensity),
+                ),
+            );
+            if elevation > 0.0 {
+                let sun_intensity = arg_or_scene(
+                    &matches,
+                    "sun_intensity",
+                    scene.as_ref().and_then(|s| s.sun_intensity),
+                );
+                sun_light = Some(
+                    Light::new(to_vec3((0.0, 0.0, 0.0)), sun_intensity)
+                        .with_directional(-sky.sun_direction)
+                        .with_color(sky.sun_transmittance()),
+                );
+            }
+            Background::Sky(sky)
+        }
+        None => match background_mode {
+            BackgroundMode::Solid => Background::Solid(background_color),
+            BackgroundMode::Gradient => Background::Gradient {
+                top: background_top,
+                bottom: background_bottom,
+            },
+        },
+    };
+    let camera_pos_from_scene = scene.as_ref().and_then(|s| s.camera_pos);
+    let up = triple_arg_or_scene(&matches, "up", scene.as_ref().and_then(|s| s.up));
+    let roll = arg_or_scene(&matches, "roll", scene.as_ref().and_then(|s| s.roll));
+    let aperture = arg_or_scene(
+        &matches,
+        "aperture",
+        scene.as_ref().and_then(|s| s.aperture),
+    );
+    let focus_distance = matches
+        .get_one::<f32>("focus_distance")
+        .copied()
+        .or(scene.as_ref().and_then(|s| s.focus_distance));
+    let projection = arg_or_scene(
+        &matches,
+        "projection",
+        scene.as_ref().and_then(|s| s.projection),
+    );
+    let view_width = arg_or_scene(
+        &matches,
+        "view_width",
+        scene.as_ref().and_then(|s| s.view_width),
     );
+    let fisheye_fov = arg_or_scene(
+        &matches,
+        "fisheye_fov",
+        scene.as_ref().and_then(|s| s.fisheye_fov),
+    );
+
+    let (spheres, meshes, mut lights) = match scene {
+        Some(scene) => (scene.spheres, scene.meshes, scene.lights),
+        None => {
+            let (spheres, lights) = match preset {
+                1 => setup::get_spheres_lights_1(),
+                2 => setup::get_spheres_lights_2(),
+                3 => setup::get_spheres_lights_3(),
+                4 => setup::get_spheres_lights_4(),
+                5 => setup::get_spheres_lights_5(),
+                6 => setup::get_spheres_lights_6(),
+                _ => setup::get_spheres_lights_1(),
+            };
+            (spheres, Vec::new(), lights)
+        }
+    };
+
+    lights.extend(sun_light);
+
+    let mut shapes: Vec<Box<dyn Shape>> = Vec::new();
+    if floor.enabled {
+        shapes.push(Box::new(setup::build_floor(&floor)));
+    }
+    for sphere in spheres {
+        shapes.push(Box::new(sphere));
+    }
+    for mesh in meshes {
+        for triangle in mesh.triangles {
+            shapes.push(Box::new(triangle));
+        }
+    }
+
+    let settings = RenderSettings {
+        resolution: (width, height),
+        max_depth: max_depth,
+        offset_for_mitigating_occlusion: occlusion_offset,
+        sampler: sampler,
+        adaptive: adaptive,
+        seed: seed,
+        filter: filter,
+        shadow_samples: shadow_samples,
+        use_bvh: use_bvh,
+        integrator: integrator,
+        path_samples: path_samples,
+        tone_mapper: tone_mapper,
+        threads: threads,
+    };
+    let tracer = raytracer::Raytracer::new(settings, shapes, lights, background, backplate);
 
     match matches.subcommand() {
         Some(("img", sub_matches)) => {
-            let camera_pos_tmp: Vec<Vec<&i32>> = sub_matches
-                .get_occurrences("camera_pos")
-                .unwrap()
-                .map(Iterator::collect)
-                .collect();
-            let camera_pos: (f32, f32, f32) = (
-                *camera_pos_tmp[0][0] as f32,
-                *camera_pos_tmp[0][1] as f32,
-                *camera_pos_tmp[0][2] as f32,
-            );
-            tracer.render_single_image(camera_pos, look_at_point, output_path, versionize);
+            let camera_pos = triple_arg_or_scene(sub_matches, "camera_pos", camera_pos_from_scene);
+            let format = *sub_matches.get_one::<OutputFormat>("format").unwrap();
+            let sample_map = sub_matches.get_flag("sample_map");
+            let camera = Camera::new(
+                to_vec3(camera_pos),
+                to_vec3(look_at_point),
+                to_vec3(up),
+                fov,
+                width as f32 / height as f32,
+                roll,
+            )
+            .with_lens(aperture, focus_distance)
+            .with_projection(projection, view_width, fisheye_fov);
+            match sub_matches.get_one::<StereoMode>("stereo") {
+                Some(mode) => {
+                    let eye_separation = *sub_matches.get_one::<f32>("eye_separation").unwrap();
+                    let convergence = sub_matches
+                        .get_one::<f32>("convergence")
+                        .copied()
+                        .unwrap_or((camera.target - camera.eye).norm());
+                    tracer.render_stereo_image(
+                        &camera,
+                        eye_separation,
+                        convergence,
+                        *mode,
+                        output_path,
+                        versionize,
+                        format,
+                        sample_map,
+                    );
+                }
+                None => {
+                    tracer.render_single_image(&camera, output_path, versionize, format, sample_map)
+                }
+            }
         }
         Some(("gif", sub_matches)) => {
-            let y_level = *sub_matches.get_one::<i32>("y_level").unwrap();
+            let y_level = *sub_matches.get_one::<f32>("y_level").unwrap();
             let radius = *sub_matches.get_one::<f32>("radius").unwrap();
             let num_of_images = *sub_matches.get_one::<u32>("num_of_images").unwrap();
-            tracer.rotate_cam_around_point_and_render_images(look_at_point, y_level, radius, num_of_images, output_path, versionize);
+            // camera at the position of the first frame, it is moved along the orbit
+            let camera = Camera::new(
+                to_vec3((look_at_point.0, y_level, look_at_point.2 + radius)),
+                to_vec3(look_at_point),
+                to_vec3(up),
+                fov,
+                width as f32 / height as f32,
+                roll,
+            )
+            .with_lens(aperture, focus_distance)
+            .with_projection(projection, view_width, fisheye_fov);
+            let lens_end = (
+                sub_matches
+                    .get_one::<f32>("aperture_end")
+                    .copied()
+                    .unwrap_or(camera.aperture),
+                sub_matches
+                    .get_one::<f32>("focus_distance_end")
+                    .copied()
+                    .unwrap_or(camera.focus()),
+            );
+            tracer.rotate_cam_around_point_and_render_images(
+                &camera,
+                y_level,
+                radius,
+                num_of_images,
+                lens_end,
+                output_path,
+                versionize,
+            );
         }
         _ => {}
     }
@@ -1,205 +1,608 @@
+use crate::utils::background::{Background, Backplate};
+use crate::utils::bvh::Bvh;
+use crate::utils::camera::Camera;
+use crate::utils::filter::Filter;
 use crate::utils::light::Light;
-use crate::utils::vec3::vec3;
-use crate::utils::sphere::Sphere;
 use crate::utils::material::Material;
+use crate::utils::sampler::{AdaptiveSampling, SamplePattern, Sampler};
+use crate::utils::sampling;
+use crate::utils::shape::{Hit, Shape};
+use crate::utils::tonemap::ToneMapper;
+use crate::utils::vec3::vec3;
 
 use chrono::{Datelike, Timelike};
 use image::RgbImage;
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Edge length (in pixels) of the square tiles the image is split into for rendering
+const TILE_SIZE: u32 = 32;
+
+/// Max. number of bounces of a path traced by the path integrator
+const MAX_PATH_DEPTH: u32 = 64;
+
+/// Number of bounces after which paths are terminated by russian roulette
+const RUSSIAN_ROULETTE_DEPTH: u32 = 3;
+
+/// Min. sum of the filter weights of a pixel. Filters with negative lobes can cancel out to
+/// (almost) zero, such pixels are the plain average of their own samples instead.
+const MIN_FILTER_WEIGHT: f32 = 1e-3;
+
+/// Algorithm used for calculating the color seen along a ray
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Integrator {
+    /// Whitted-style ray tracing: one reflection and one refraction ray per hit, Phong shading
+    /// against the point lights and no indirect diffuse light
+    Whitted,
+    /// Monte Carlo path tracing with cosine-weighted sampling of diffuse surfaces, russian
+    /// roulette termination and emissive surfaces
+    Path,
+}
+
+/// File format of rendered single images
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    /// 8 bit per channel PNG, colors above 1.0 are clipped
+    Png,
+    /// OpenEXR with 32 bit float channels holding the unclamped linear radiance
+    Exr,
+    /// Radiance RGBE (`.hdr`) holding the unclamped linear radiance
+    Hdr,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Exr => "exr",
+            OutputFormat::Hdr => "hdr",
+        }
+    }
+}
+
+/// Arrangement of the two views of a stereo image
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StereoMode {
+    /// Left view in the left half, right view in the right half (twice the width)
+    SideBySide,
+    /// Left view in the upper half, right view in the lower half (twice the height)
+    TopBottom,
+    /// Red channel from the left view, green and blue channels from the right view
+    Anaglyph,
+}
+
+/// A rectangular region of the image given by its upper left corner (inclusive) and lower
+/// right corner (exclusive)
+#[derive(Debug, Copy, Clone)]
+struct Tile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+/// Weighted sum of the sample colors and the sum of the weights for every pixel of a tile
+/// (including the margin around it), and the unweighted sum and number of the samples taken in
+/// each pixel of the tile
+struct TileBuffer {
+    colors: Vec<vec3>,
+    weights: Vec<f32>,
+    sample_sums: Vec<vec3>,
+    sample_counts: Vec<u32>,
+}
+
+/// Joins the rows of two images of the same size, so that `left` ends up in the left half and
+/// `right` in the right half
+fn side_by_side<T: Copy>(left: &[T], right: &[T], width: u32) -> Vec<T> {
+    return left
+        .chunks(width as usize)
+        .zip(right.chunks(width as usize))
+        .flat_map(|(l, r)| l.iter().chain(r.iter()).copied())
+        .collect();
+}
+
+/// Settings of a `Raytracer` that do not depend on the scene
+pub struct RenderSettings {
+    pub resolution: (u32, u32),
+    pub max_depth: u32,
+    pub offset_for_mitigating_occlusion: f32,
+    pub sampler: Sampler,
+    pub adaptive: Option<AdaptiveSampling>,
+    pub seed: u64,
+    pub filter: Filter,
+    /// Number of shadow rays per area light
+    pub shadow_samples: u32,
+    /// Accelerate the intersection tests with a bounding volume hierarchy
+    pub use_bvh: bool,
+    pub integrator: Integrator,
+    pub path_samples: u32,
+    pub tone_mapper: ToneMapper,
+    pub threads: usize,
+}
 
 pub struct Raytracer {
     width: u32,
     height: u32,
-    background_color: vec3,
-    floor_dimensions: (f32, f32),
-    floor_color: vec3,
-    floor_level: f32,
+    background: Background,
+    backplate: Option<Backplate>,
     max_depth: u32,
     offset_for_mitigating_occlusion: f32,
-    anti_aliasing_offsets: Vec<(f32, f32)>,
-    fov: f32,
-    spheres: Vec<Sphere>,
+    sampler: Sampler,
+    adaptive: Option<AdaptiveSampling>,
+    seed: u64,
+    filter: Filter,
+    shapes: Vec<Box<dyn Shape>>,
     lights: Vec<Light>,
+    /// Positions of the shadow rays on area lights (jittered over the light)
+    shadow_sampler: Sampler,
+    bvh: Option<Bvh>,
+    integrator: Integrator,
+    path_samples: u32,
+    tone_mapper: ToneMapper,
+    pool: rayon::ThreadPool,
 }
 
 impl Raytracer {
     pub fn new(
-        resolution: (u32, u32),
-        floor_dimensions: (f32, f32),
-        background_color: (u32, u32, u32),
-        floor_color: (u32, u32, u32),
-        floor_level: f32,
-        max_depth: u32,
-        offset_for_mitigating_occlusion: f32,
-        anti_aliasing: u32,
-        fov: f32,
-        spheres: Vec<Sphere>,
+        settings: RenderSettings,
+        shapes: Vec<Box<dyn Shape>>,
         lights: Vec<Light>,
+        background: Background,
+        backplate: Option<Backplate>,
     ) -> Raytracer {
-        let mut anti_aliasing_offsets: Vec<(f32, f32)> = vec![];
-        for i in 1..anti_aliasing + 1 {
-            for j in 1..anti_aliasing + 1 {
-                anti_aliasing_offsets.push((
-                    f32::round(100.0 * (j as f32 / (anti_aliasing + 1) as f32)) / 100.0,
-                    f32::round(100.0 * (-(i as f32) / (anti_aliasing + 1) as f32)) / 100.0,
-                ));
-            }
-        }
+        let bvh = if settings.use_bvh {
+            Some(Bvh::build(&shapes))
+        } else {
+            None
+        };
         return Raytracer {
-            width: resolution.0,
-            height: resolution.1,
-            floor_dimensions: floor_dimensions,
-            background_color: vec3 {
-                x: background_color.0 as f32,
-                y: background_color.1 as f32,
-                z: background_color.2 as f32,
-            } / 255.0,
-            floor_color: vec3 {
-                x: floor_color.0 as f32,
-                y: floor_color.1 as f32,
-                z: floor_color.2 as f32,
-            } / 255.0,
-            floor_level: floor_level,
-            max_depth: max_depth,
-            offset_for_mitigating_occlusion: offset_for_mitigating_occlusion,
-            anti_aliasing_offsets: anti_aliasing_offsets,
-            fov: fov,
-            spheres: spheres,
+            width: settings.resolution.0,
+            height: settings.resolution.1,
+            background: background,
+            backplate: backplate,
+            max_depth: settings.max_depth,
+            offset_for_mitigating_occlusion: settings.offset_for_mitigating_occlusion,
+            sampler: settings.sampler,
+            adaptive: settings.adaptive,
+            seed: settings.seed,
+            filter: settings.filter,
+            shapes: shapes,
             lights: lights,
+            shadow_sampler: Sampler::new(SamplePattern::Jittered, settings.shadow_samples),
+            bvh: bvh,
+            integrator: settings.integrator,
+            path_samples: settings.path_samples,
+            tone_mapper: settings.tone_mapper,
+            pool: rayon::ThreadPoolBuilder::new()
+                .num_threads(settings.threads)
+                .build()
+                .unwrap(),
         };
     }
 
-    fn reflect(&mut self, vector: vec3, axis: vec3) -> vec3 {
+    fn reflect(&self, vector: vec3, axis: vec3) -> vec3 {
         return vector - axis * (vector * axis) * 2.0;
     }
 
-    fn refract(&mut self, mut vector: vec3, mut axis: vec3, refractive_index: f32, eta_i: f32) -> vec3 {
+    /// Direction of the refracted ray, or `None` in case of total internal reflection
+    fn refract(
+        &self,
+        mut vector: vec3,
+        mut axis: vec3,
+        refractive_index: f32,
+        eta_i: f32,
+    ) -> Option<vec3> {
         let cosi = (vector * axis) / (vector.norm() * axis.norm());
-        if cosi < 0.0 {
+        if cosi <= 0.0 {
             let eta = eta_i / refractive_index;
             let tmp = 1.0 - f32::powf(eta, 2.0) + f32::powf(eta * cosi, 2.0);
-            return vector * eta - axis * eta * cosi - axis * f32::sqrt(tmp);
+            if tmp < 0.0 {
+                return None;
+            }
+            return Some(vector * eta - axis * eta * cosi - axis * f32::sqrt(tmp));
         }
         return self.refract(vector, -axis, eta_i, refractive_index);
     }
 
-    fn intersect_between_ray_and_sphere(
-        &mut self,
-        origin: vec3,
+    /// Fraction of the light that is reflected at the boundary between air and a material with
+    /// the given refractive index (Schlick's approximation of the Fresnel equations)
+    fn fresnel(&self, mut direction: vec3, mut normal: vec3, refractive_index: f32) -> f32 {
+        let mut cosi = f32::clamp(
+            (direction * normal) / (direction.norm() * normal.norm()),
+            -1.0,
+            1.0,
+        );
+        let (mut eta_i, mut eta_t) = (1.0, refractive_index);
+        if cosi > 0.0 {
+            std::mem::swap(&mut eta_i, &mut eta_t);
+        }
+        cosi = cosi.abs();
+        let sint = eta_i / eta_t * f32::sqrt(f32::max(0.0, 1.0 - cosi * cosi));
+        if sint >= 1.0 {
+            return 1.0;
+        }
+        // Schlick's approximation uses the larger of the two angles
+        let cos = if eta_i > eta_t {
+            f32::sqrt(1.0 - sint * sint)
+        } else {
+            cosi
+        };
+        let r0 = f32::powf((eta_i - eta_t) / (eta_i + eta_t), 2.0);
+        return r0 + (1.0 - r0) * f32::powf(1.0 - cos, 5.0);
+    }
+
+    /// Weights of the reflected and the refracted ray. With Fresnel enabled the sum of both
+    /// multipliers of the material is split according to the angle of incidence, and in case of
+    /// total internal reflection all of it is reflected. Otherwise the multipliers are used as
+    /// they are, so without a refracted ray its part of the light is lost.
+    fn reflection_refraction_weights(
+        &self,
         direction: vec3,
-        sphere: Sphere,
-    ) -> (bool, f32) {
-        let vec_to_center = sphere.center - origin;
-        let projection_of_dir_to_center = vec_to_center * direction;
-        let discriminant = vec_to_center * vec_to_center - f32::powf(projection_of_dir_to_center, 2.0);
-        if discriminant < f32::powf(sphere.radius, 2.0) {
-            let tmp = f32::sqrt(f32::powf(sphere.radius, 2.0) - discriminant);
-            let t0 = projection_of_dir_to_center - tmp;
-            let t1 = projection_of_dir_to_center + tmp;
-            if t0 > self.offset_for_mitigating_occlusion {
-                return (true, t0);
-            } else if t1 > self.offset_for_mitigating_occlusion {
-                return (true, t1);
-            }
+        normal: vec3,
+        material: &Material,
+        total_internal_reflection: bool,
+    ) -> (f32, f32) {
+        let (reflection, refraction) = if material.fresnel {
+            let total = material.reflection_multiplier + material.refraction_multiplier;
+            let kr = self.fresnel(direction, normal, material.refractive_index);
+            (total * kr, total * (1.0 - kr))
+        } else {
+            (
+                material.reflection_multiplier,
+                material.refraction_multiplier,
+            )
+        };
+        if total_internal_reflection && material.fresnel {
+            return (reflection + refraction, 0.0);
         }
-        return (false, 0.0);
+        return (reflection, refraction);
+    }
+
+    fn scene_interact(&self, origin: vec3, direction: vec3) -> (bool, vec3, vec3, Material) {
+        let max_dist = 1000.0;
+        let hit = match &self.bvh {
+            Some(bvh) => bvh.nearest_hit(
+                &self.shapes,
+                origin,
+                direction,
+                self.offset_for_mitigating_occlusion,
+                max_dist,
+            ),
+            None => {
+                let mut nearest: Option<Hit> = None;
+                let mut nearest_dist = max_dist;
+                for shape in self.shapes.iter() {
+                    let hit =
+                        shape.intersect(origin, direction, self.offset_for_mitigating_occlusion);
+                    if let Some(hit) = hit {
+                        if hit.distance <= nearest_dist {
+                            nearest_dist = hit.distance;
+                            nearest = Some(hit);
+                        }
+                    }
+                }
+                nearest
+            }
+        };
+
+        return match hit {
+            Some(hit) if hit.distance < max_dist => {
+                let point = origin + direction * hit.distance;
+                let mut material = hit.material.clone();
+                if let Some(color) = hit.color {
+                    material.color = color;
+                }
+                (
+                    true,
+                    point,
+                    hit.normal,
+                    material.textured(hit.uv, point, hit.object_point),
+                )
+            }
+            _ => (
+                false,
+                vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                Material::default(),
+            ),
+        };
+    }
+
+    fn map_range(&self, from_range: (f32, f32), to_range: (f32, f32), s: f32) -> f32 {
+        to_range.0 + (s - from_range.0) * (to_range.1 - to_range.0) / (from_range.1 - from_range.0)
+    }
 
+    fn background(&self, direction: vec3) -> vec3 {
+        return match &self.background {
+            Background::Solid(color) => *color,
+            Background::Gradient { top, bottom } => {
+                let t = self.map_range((-1.0, 1.0), (0.0, 1.0), direction.y);
+                *bottom + (*top - *bottom) * t
+            }
+            Background::Environment(map) => map.radiance(direction),
+            Background::Sky(sky) => sky.radiance(direction),
+        };
+    }
+
+    /// `true` if the background lights the scene (and is sampled in `direct_light`)
+    fn has_environment_light(&self) -> bool {
+        return matches!(self.background, Background::Environment(_));
     }
 
-    fn scene_interact(&mut self, origin: vec3, direction: vec3) -> (bool, vec3, vec3, Material) {
-        let mut point = vec3 {
+    /// Diffuse light at `point` arriving from the environment map, estimated with one shadow
+    /// ray per sample of the shadow sampler. The directions are importance sampled according
+    /// to the brightness of the map.
+    fn environment_light(
+        &self,
+        point: vec3,
+        normal: vec3,
+        direction: vec3,
+        rng: &mut SmallRng,
+    ) -> vec3 {
+        let mut light = vec3 {
             x: 0.0,
             y: 0.0,
             z: 0.0,
         };
-        let mut normal = vec3 {
+        let map = match &self.background {
+            Background::Environment(map) => map,
+            _ => return light,
+        };
+        let facing_normal = if direction * normal > 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        let samples = self.shadow_sampler.pixel_samples(rng);
+        let sample_weight = 1.0 / samples.len() as f32;
+        for (u1, u2) in samples {
+            let (light_dir, radiance, pdf) = map.sample(u1, u2);
+            let cos_theta = light_dir * facing_normal;
+            if pdf <= 0.0 || cos_theta <= 0.0 {
+                continue;
+            }
+            let (hit, _, _, _) = self.scene_interact(point, light_dir);
+            if !hit {
+                // Lambertian reflection: radiance * cos / (pi * pdf)
+                light += radiance * (cos_theta / (std::f32::consts::PI * pdf) * sample_weight);
+            }
+        }
+        return light;
+    }
+
+    /// Diffuse and specular light (RGB) at `point` (Phong model) caused by all lights that are
+    /// not occluded. Area lights are sampled with one shadow ray per sample of the
+    /// shadow sampler, so partially occluded lights produce soft shadows. The diffuse light is
+    /// the irradiance `intensity * cos` (times the attenuation of the light).
+    fn direct_light(
+        &self,
+        point: vec3,
+        normal: vec3,
+        direction: vec3,
+        material: &Material,
+        rng: &mut SmallRng,
+    ) -> (vec3, vec3) {
+        let mut diffuse_light = vec3 {
             x: 0.0,
             y: 0.0,
             z: 0.0,
         };
-        let mut material = Material::default();
-        let mut nearest_dist = 1000.0;
-
-        if direction.y.abs() > 0.0 {
-            let distance = -(origin.y + (-self.floor_level)) / direction.y;
-            let tmp_point = origin + direction * distance;
-
-            let floor_hit = distance > self.offset_for_mitigating_occlusion
-                && distance < nearest_dist
-                && f32::abs(tmp_point.x) < self.floor_dimensions.0
-                && (-10.0 - self.floor_dimensions.1) < tmp_point.z
-                && tmp_point.z < -10.0;
-
-            if floor_hit {
-                nearest_dist = distance;
-                point = tmp_point;
-                normal = vec3 {
-                    x: 0.0,
-                    y: 1.0,
-                    z: 0.0,
-                };
-                material.color = self.floor_color;
-            }
-        }
+        let mut specular_light = diffuse_light;
+        for light in self.lights.iter() {
+            let area_samples;
+            let light_samples: &[(f32, f32)] = if light.is_point() {
+                &[(0.0, 0.0)]
+            } else {
+                area_samples = self.shadow_sampler.pixel_samples(rng);
+                &area_samples
+            };
+            let sample_weight = 1.0 / light_samples.len() as f32;
+            for &(u1, u2) in light_samples {
+                let (light_dir, light_distance, emission) = light.sample(point, u1, u2);
+                if emission <= 0.0 {
+                    continue;
+                }
+                let light_color = light.color * (light.intensity * emission * sample_weight);
+                let (hit, shadow_pt, _, _) = self.scene_interact(point, light_dir);
 
-        for s in self.spheres.clone() {
-            let (intersection, distance) =
-                self.intersect_between_ray_and_sphere(origin, direction, s);
-            if intersection && distance <= nearest_dist {
-                nearest_dist = distance;
-                point = origin + direction * nearest_dist;
-                normal = (point - s.center).normalize();
-                material = s.material;
+                if !(hit && (shadow_pt - point).norm() < light_distance) {
+                    diffuse_light += light_color * f32::max(0.0, light_dir * normal);
+                    let tmp_base = f32::max(0.0, -self.reflect(-light_dir, normal) * direction);
+                    specular_light += light_color * f32::powf(tmp_base, material.specular_exponent);
+                }
             }
         }
-        return (nearest_dist < 1000.0, point, normal, material);
+        return (diffuse_light, specular_light);
     }
 
-    fn map_range(&mut self, from_range: (f32, f32), to_range: (f32, f32), s: f32) -> f32 {
-        to_range.0 + (s - from_range.0) * (to_range.1 - to_range.0) / (from_range.1 - from_range.0)
-    }
-
-    fn cast_ray(&mut self, origin: vec3, direction: vec3, depth: u32) -> vec3 {
+    /// Color seen along the ray, traced recursively up to `max_depth` reflections and
+    /// refractions. `backplate` is the color of the backplate behind a camera ray, which is
+    /// seen instead of the background if the ray misses the scene.
+    fn cast_ray(
+        &self,
+        origin: vec3,
+        direction: vec3,
+        depth: u32,
+        backplate: Option<vec3>,
+        rng: &mut SmallRng,
+    ) -> vec3 {
         let (hit, point, normal, material) = self.scene_interact(origin, direction);
         if depth == self.max_depth || !hit {
-            let mut bc = self.map_range((-1.0, 1.0), (0.0, 0.8), direction.y);
-            bc = ((100.0 * bc) as u32) as f32 / 100.0;
-            return vec3 {
-                x: bc * self.background_color.x,
-                y: bc * self.background_color.y,
-                z: bc * self.background_color.z,
+            return match backplate {
+                Some(color) if !hit => color,
+                _ => self.background(direction),
             };
         }
 
         let direction_of_reflection = self.reflect(direction, normal).normalize();
-        let direction_of_refraction = self
-            .refract(direction, normal, material.refractive_index, 1.0)
-            .normalize();
-        let color_of_reflection = self.cast_ray(point, direction_of_reflection, depth + 1);
-        let color_of_refraction = self.cast_ray(point, direction_of_refraction, depth + 1);
-
-        let mut diffuse_light_intensity = 0.0;
-        let mut specular_light_intensity = 0.0;
-        for light in self.lights.clone() {
-            let light_dir = (light.pos - point).normalize();
-            let (hit, shadow_pt, _, _) = self.scene_interact(point, light_dir);
-
-            if !(hit && (shadow_pt - point).norm() < (light.pos - point).norm()) {
-                diffuse_light_intensity += f32::max(0.0, light_dir * normal) * light.intensity;
-                let tmp_base = f32::max(0.0, -self.reflect(-light_dir, normal) * direction);
-                specular_light_intensity +=
-                    f32::powf(tmp_base, material.specular_exponent) * light.intensity;
-            }
+        let direction_of_refraction =
+            self.refract(direction, normal, material.refractive_index, 1.0);
+        let (reflection_weight, refraction_weight) = self.reflection_refraction_weights(
+            direction,
+            normal,
+            &material,
+            direction_of_refraction.is_none(),
+        );
+        let color_of_reflection =
+            self.cast_ray(point, direction_of_reflection, depth + 1, None, rng);
+        let color_of_refraction = match direction_of_refraction {
+            Some(mut direction_of_refraction) => self.cast_ray(
+                point,
+                direction_of_refraction.normalize(),
+                depth + 1,
+                None,
+                rng,
+            ),
+            None => vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+
+        let (mut diffuse_light, specular_light) =
+            self.direct_light(point, normal, direction, &material, rng);
+        if self.has_environment_light() {
+            diffuse_light += self.environment_light(point, normal, direction, rng);
         }
-        let diffuse_color = material.color * diffuse_light_intensity * material.diffuse_multiplier;
+        let diffuse_color =
+            material.color.mul_elementwise(diffuse_light) * material.diffuse_multiplier;
         let specular_color =
-            material.color * specular_light_intensity * material.specular_multiplier;
-        let reflection_color = color_of_reflection * material.reflection_multiplier;
-        let refraction_color = color_of_refraction * material.refraction_multiplier;
+            material.color.mul_elementwise(specular_light) * material.specular_multiplier;
+        let reflection_color = color_of_reflection * reflection_weight;
+        let refraction_color = color_of_refraction * refraction_weight;
 
-        return diffuse_color + specular_color + reflection_color + refraction_color;
+        return diffuse_color
+            + specular_color
+            + reflection_color
+            + refraction_color
+            + material.emission;
     }
 
-    fn save_image(&mut self, img: RgbImage, path: &str, versionize: bool) {
+    /// Traces a single path through the scene. At every hit the light from the lights is added
+    /// directly (Lambertian, without the specular highlights of the Phong model), then the
+    /// path is continued by randomly choosing between diffuse
+    /// reflection, mirror reflection and refraction (weighted by the multipliers of the
+    /// material). An environment map is sampled directly as well, so it is only added when a
+    /// path leaves the scene after a mirror reflection or refraction (or directly from the
+    /// camera). `backplate` is used as in `cast_ray`.
+    fn trace_path(
+        &self,
+        mut origin: vec3,
+        mut direction: vec3,
+        backplate: Option<vec3>,
+        rng: &mut SmallRng,
+    ) -> vec3 {
+        let mut radiance = vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let mut throughput = vec3 {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        };
+
+        let mut after_diffuse_bounce = false;
+        for depth in 0..MAX_PATH_DEPTH {
+            let (hit, point, normal, material) = self.scene_interact(origin, direction);
+            if !hit {
+                match backplate {
+                    Some(color) if depth == 0 => radiance += color,
+                    _ if !(after_diffuse_bounce && self.has_environment_light()) => {
+                        radiance += throughput.mul_elementwise(self.background(direction));
+                    }
+                    _ => {}
+                }
+                break;
+            }
+            radiance += throughput.mul_elementwise(material.emission);
+
+            let mut diffuse_albedo = material.color * material.diffuse_multiplier;
+            diffuse_albedo = vec3 {
+                x: f32::min(diffuse_albedo.x, 1.0),
+                y: f32::min(diffuse_albedo.y, 1.0),
+                z: f32::min(diffuse_albedo.z, 1.0),
+            };
+
+            // Lambertian reflection of the irradiance of the lights: albedo / pi * irradiance,
+            // the estimate of the environment map already contains the 1 / pi
+            let (irradiance, _) = self.direct_light(point, normal, direction, &material, rng);
+            let mut diffuse_light = irradiance / std::f32::consts::PI;
+            if self.has_environment_light() {
+                diffuse_light += self.environment_light(point, normal, direction, rng);
+            }
+            radiance += throughput.mul_elementwise(diffuse_albedo.mul_elementwise(diffuse_light));
+            let direction_of_refraction =
+                self.refract(direction, normal, material.refractive_index, 1.0);
+            let (reflection_weight, refraction_weight) = self.reflection_refraction_weights(
+                direction,
+                normal,
+                &material,
+                direction_of_refraction.is_none(),
+            );
+            let diffuse_weight = diffuse_albedo.max_component();
+            let total_weight = diffuse_weight + reflection_weight + refraction_weight;
+            if total_weight <= 0.0 {
+                break;
+            }
+
+            let choice = rng.gen::<f32>() * total_weight;
+            origin = point;
+            after_diffuse_bounce = choice < diffuse_weight;
+            if choice < diffuse_weight {
+                let facing_normal = if direction * normal > 0.0 {
+                    -normal
+                } else {
+                    normal
+                };
+                direction =
+                    sampling::cosine_weighted_hemisphere(facing_normal, rng.gen(), rng.gen());
+                throughput =
+                    throughput.mul_elementwise(diffuse_albedo) * (total_weight / diffuse_weight);
+            } else {
+                direction = if choice < diffuse_weight + reflection_weight {
+                    self.reflect(direction, normal).normalize()
+                } else {
+                    match direction_of_refraction {
+                        Some(mut direction_of_refraction) => direction_of_refraction.normalize(),
+                        None => break,
+                    }
+                };
+                throughput = throughput * total_weight;
+            }
+
+            if depth >= RUSSIAN_ROULETTE_DEPTH {
+                let survival_probability = f32::min(throughput.max_component(), 0.95);
+                if rng.gen::<f32>() >= survival_probability {
+                    break;
+                }
+                throughput = throughput / survival_probability;
+            }
+        }
+        return radiance;
+    }
+
+    /// Path of the output file inside the folder `path` (which is created if necessary), named
+    /// `<name>.<extension>` or prefixed with the current datetime if `versionize` is set
+    fn output_file_path(
+        &self,
+        path: &str,
+        versionize: bool,
+        name: &str,
+        extension: &str,
+    ) -> std::path::PathBuf {
         let mut path_buf = std::path::PathBuf::new();
         if !std::path::Path::new(path).exists() {
             std::fs::create_dir_all(path).unwrap();
@@ -213,41 +616,86 @@ impl Raytracer {
             let minute = chrono::Local::now().minute();
             let second = chrono::Local::now().second();
             let img_name = format!(
-                "{}-{:0>2}-{:0>2}_{:0>2}{:0>2}{:0>2}_out.png",
-                year, month, day, hour, minute, second
+                "{}-{:0>2}-{:0>2}_{:0>2}{:0>2}{:0>2}_{}.{}",
+                year, month, day, hour, minute, second, name, extension
             );
             path_buf.push(img_name);
         } else {
-            path_buf.push("out.png");
+            path_buf.push(format!("{}.{}", name, extension));
         }
+        return path_buf;
+    }
 
-        match img.save(path_buf.to_str().unwrap()) {
+    /// Saves a framebuffer of `width` x `height` pixels
+    fn save_image(
+        &self,
+        framebuffer: Vec<vec3>,
+        (width, height): (u32, u32),
+        path: &str,
+        versionize: bool,
+        format: OutputFormat,
+    ) {
+        let path_buf = self.output_file_path(path, versionize, "out", format.extension());
+
+        let result =
+            match format {
+                OutputFormat::Png => self
+                    .framebuffer_to_rgbimage(framebuffer, (width, height))
+                    .save(&path_buf),
+                OutputFormat::Exr => {
+                    let mut img = image::Rgb32FImage::new(width, height);
+                    for (i, color) in framebuffer.into_iter().enumerate() {
+                        let w = i as u32 % width;
+                        let h = i as u32 / width;
+                        img.put_pixel(w, h, image::Rgb([color.x, color.y, color.z]));
+                    }
+                    img.save(&path_buf)
+                }
+                OutputFormat::Hdr => {
+                    let pixels: Vec<image::Rgb<f32>> = framebuffer
+                        .into_iter()
+                        .map(|color| image::Rgb([color.x, color.y, color.z]))
+                        .collect();
+                    std::fs::File::create(&path_buf)
+                        .map_err(image::ImageError::IoError)
+                        .and_then(|file| {
+                            image::codecs::hdr::HdrEncoder::new(std::io::BufWriter::new(file))
+                                .encode(&pixels, width as usize, height as usize)
+                        })
+                }
+            };
+        match result {
             Err(e) => println!("{:?}", e),
             _ => println!("Saved image to '{}'", path_buf.to_str().unwrap()),
         }
     }
 
-    fn save_gif(&mut self, frames: Vec<gif::Frame>, path: &str, versionize: bool) {
-        let mut path_buf = std::path::PathBuf::new();
-        if !std::path::Path::new(path).exists() {
-            std::fs::create_dir_all(path).unwrap();
+    /// Saves a grayscale image of the number of samples spent per pixel, white being the max.
+    /// number of samples of the sampler
+    fn save_sample_map(
+        &self,
+        sample_counts: Vec<u32>,
+        (width, height): (u32, u32),
+        path: &str,
+        versionize: bool,
+    ) {
+        let path_buf = self.output_file_path(path, versionize, "samples", "png");
+
+        let mut img = RgbImage::new(width, height);
+        for (i, count) in sample_counts.into_iter().enumerate() {
+            let w = i as u32 % width;
+            let h = i as u32 / width;
+            let value = (count * 255 / self.sampler.samples()) as u8;
+            img.put_pixel(w, h, image::Rgb([value, value, value]));
         }
-        path_buf.push(path);
-        if versionize {
-            let year = chrono::Local::now().year();
-            let month = chrono::Local::now().month();
-            let day = chrono::Local::now().day();
-            let hour = chrono::Local::now().hour();
-            let minute = chrono::Local::now().minute();
-            let second = chrono::Local::now().second();
-            let img_name = format!(
-                "{}-{:0>2}-{:0>2}_{:0>2}{:0>2}{:0>2}_out.gif",
-                year, month, day, hour, minute, second
-            );
-            path_buf.push(img_name);
-        } else {
-            path_buf.push("out.gif");
+        match img.save(&path_buf) {
+            Err(e) => println!("{:?}", e),
+            _ => println!("Saved sample map to '{}'", path_buf.to_str().unwrap()),
         }
+    }
+
+    fn save_gif(&self, frames: Vec<gif::Frame>, path: &str, versionize: bool) {
+        let path_buf = self.output_file_path(path, versionize, "out", "gif");
 
         let image = std::fs::File::create(path_buf.to_str().unwrap()).unwrap();
         let mut encoder =
@@ -261,40 +709,92 @@ impl Raytracer {
         println!("Saved gif to '{}'", path_buf.to_str().unwrap());
     }
 
-    fn calc_color_at_pixel(
-        &mut self,
-        w: u32,
-        h: u32,
-        dir_z: f32,
-        from: vec3,
-        to: vec3,
-    ) -> [u8; 3] {
-        let mut color = vec3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
+    /// Color seen by a single ray through the point (`u`, `v`) of the image (see
+    /// `Camera::direction`)
+    fn calc_color_of_sample(&self, camera: &Camera, u: f32, v: f32, rng: &mut SmallRng) -> vec3 {
+        let ray = if camera.has_lens() {
+            Some(camera.lens_ray(u, v, rng.gen(), rng.gen()))
+        } else {
+            camera.ray(u, v)
         };
-        for i in 0..self.anti_aliasing_offsets.len() {
-            let dir_x =
-                w as f32 - (self.width as f32 / 2.0).floor() + self.anti_aliasing_offsets[i].0;
-            let dir_y =
-                (self.height as f32 / 2.0).floor() - h as f32 - self.anti_aliasing_offsets[i].1;
-            color += self.cast_ray(
-                from,
-                vec3::look_at(
-                    from,
-                    to,
-                    vec3 {
-                        x: dir_x,
-                        y: dir_y,
-                        z: dir_z,
-                    },
-                )
-                .normalize(),
-                0,
-            );
+        let (from, direction) = match ray {
+            Some(ray) => ray,
+            None => {
+                return vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                }
+            }
+        };
+        let backplate = self.backplate.as_ref().map(|backplate| {
+            // position relative to the upper left corner of the image (inverse of the mapping
+            // in `calc_samples_at_pixel`)
+            let x = u * (self.width as f32 / 2.0) + (self.width as f32 / 2.0).floor();
+            let y = (self.height as f32 / 2.0).floor() - v * (self.height as f32 / 2.0) + 1.0;
+            backplate.color(x / self.width as f32, y / self.height as f32)
+        });
+        return match self.integrator {
+            Integrator::Whitted => self.cast_ray(from, direction, 0, backplate, rng),
+            Integrator::Path => {
+                let mut path_color = vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                };
+                for _ in 0..self.path_samples {
+                    path_color += self.trace_path(from, direction, backplate, rng);
+                }
+                path_color / self.path_samples as f32
+            }
+        };
+    }
+
+    /// Samples of the pixel as their position inside the pixel and their color. With adaptive
+    /// sampling enabled the pixel is refined until the standard error of the mean (tone mapped)
+    /// luminance drops below the threshold or all samples of the sampler are used.
+    fn calc_samples_at_pixel(&self, w: u32, h: u32, camera: &Camera) -> Vec<(f32, f32, vec3)> {
+        let mut rng = SmallRng::seed_from_u64(
+            (((h as u64) << 32) | w as u64) ^ self.seed.wrapping_mul(0x9E3779B97F4A7C15),
+        );
+        let mut offsets = self.sampler.pixel_samples(&mut rng);
+        if self.adaptive.is_some() && !self.sampler.is_progressive() {
+            // every prefix of the samples should cover the whole pixel
+            offsets.shuffle(&mut rng);
         }
-        color = (color / self.anti_aliasing_offsets.len() as f32) * 255.0;
+
+        let mut samples: Vec<(f32, f32, vec3)> = Vec::new();
+        let mut mean_luminance = 0.0;
+        let mut squared_deviations = 0.0;
+        for (offset_x, offset_y) in offsets {
+            let dir_x = w as f32 - (self.width as f32 / 2.0).floor() + offset_x;
+            let dir_y = (self.height as f32 / 2.0).floor() - h as f32 + offset_y;
+            let u = dir_x / (self.width as f32 / 2.0);
+            let v = dir_y / (self.height as f32 / 2.0);
+            let sample_color = self.calc_color_of_sample(camera, u, v, &mut rng);
+            samples.push((offset_x, offset_y, sample_color));
+
+            if let Some(adaptive) = &self.adaptive {
+                // Welford's online algorithm for the variance
+                let count = samples.len() as u32;
+                let luminance = self.tone_mapper.apply(sample_color).luminance();
+                let delta = luminance - mean_luminance;
+                mean_luminance += delta / count as f32;
+                squared_deviations += delta * (luminance - mean_luminance);
+                if count >= adaptive.min_samples && count > 1 {
+                    let variance = squared_deviations / (count - 1) as f32;
+                    if f32::sqrt(variance / count as f32) <= adaptive.threshold {
+                        break;
+                    }
+                }
+            }
+        }
+        return samples;
+    }
+
+    /// Converts a linear color to 8 bit per channel after tone mapping and gamma encoding it
+    fn to_rgb8(&self, color: vec3) -> [u8; 3] {
+        let color = self.tone_mapper.apply(color) * 255.0;
         return [
             color.x.floor() as u8,
             color.y.floor() as u8,
@@ -303,60 +803,275 @@ impl Raytracer {
     }
 
     pub fn render_single_image(
-        &mut self,
-        from: (f32, f32, f32),
-        to: (f32, f32, f32),
+        &self,
+        camera: &Camera,
         path: &str,
         versionize: bool,
+        format: OutputFormat,
+        sample_map: bool,
     ) {
-        let from_vec = vec3 {
-            x: from.0,
-            y: from.1,
-            z: from.2,
+        let (framebuffer, sample_counts) = self.render_pixels(camera, None);
+        self.save_image(
+            framebuffer,
+            (self.width, self.height),
+            path,
+            versionize,
+            format,
+        );
+        if sample_map {
+            self.save_sample_map(sample_counts, (self.width, self.height), path, versionize);
+        }
+    }
+
+    /// Renders the views of the left and the right eye (see `Camera::stereo_pair`) and saves
+    /// them combined into one image according to `mode`
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_stereo_image(
+        &self,
+        camera: &Camera,
+        eye_separation: f32,
+        convergence: f32,
+        mode: StereoMode,
+        path: &str,
+        versionize: bool,
+        format: OutputFormat,
+        sample_map: bool,
+    ) {
+        let (left_camera, right_camera) = camera.stereo_pair(eye_separation, convergence);
+        let (left, left_counts) = self.render_pixels(&left_camera, Some("Calculating left view"));
+        let (right, right_counts) =
+            self.render_pixels(&right_camera, Some("Calculating right view"));
+
+        let (size, framebuffer, sample_counts) = match mode {
+            StereoMode::SideBySide => (
+                (2 * self.width, self.height),
+                side_by_side(&left, &right, self.width),
+                side_by_side(&left_counts, &right_counts, self.width),
+            ),
+            StereoMode::TopBottom => (
+                (self.width, 2 * self.height),
+                [left, right].concat(),
+                [left_counts, right_counts].concat(),
+            ),
+            StereoMode::Anaglyph => {
+                let framebuffer = left
+                    .into_iter()
+                    .zip(right)
+                    .map(|(l, r)| vec3 {
+                        x: l.x,
+                        y: r.y,
+                        z: r.z,
+                    })
+                    .collect();
+                let counts = left_counts
+                    .into_iter()
+                    .zip(right_counts)
+                    .map(|(l, r)| u32::max(l, r))
+                    .collect();
+                ((self.width, self.height), framebuffer, counts)
+            }
         };
-        let to_vec = vec3 {
-            x: to.0,
-            y: to.1,
-            z: to.2,
+        self.save_image(framebuffer, size, path, versionize, format);
+        if sample_map {
+            self.save_sample_map(sample_counts, size, path, versionize);
+        }
+    }
+
+    /// Renders the pixels of the tile and adds the weighted samples to a buffer covering the
+    /// tile plus a margin of `margin` pixels on every side, since the reconstruction filter lets
+    /// samples contribute to the neighbouring pixels
+    fn render_tile(&self, tile: Tile, margin: u32, camera: &Camera) -> TileBuffer {
+        let buffer_width = tile.x1 - tile.x0 + 2 * margin;
+        let buffer_height = tile.y1 - tile.y0 + 2 * margin;
+        let mut buffer = TileBuffer {
+            colors: vec![
+                vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                };
+                (buffer_width * buffer_height) as usize
+            ],
+            weights: vec![0.0; (buffer_width * buffer_height) as usize],
+            sample_sums: Vec::new(),
+            sample_counts: Vec::new(),
         };
-        let img = self.render_image_rgbimage(from_vec, to_vec);
-        self.save_image(img, path, versionize);
+
+        for h in tile.y0..tile.y1 {
+            for w in tile.x0..tile.x1 {
+                let samples = self.calc_samples_at_pixel(w, h, camera);
+                buffer.sample_sums.push(samples.iter().fold(
+                    vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    },
+                    |sum, (_, _, color)| sum + *color,
+                ));
+                buffer.sample_counts.push(samples.len() as u32);
+                let (bw, bh) = (w - tile.x0 + margin, h - tile.y0 + margin);
+                if self.filter.is_pixel_box() {
+                    let i = (bh * buffer_width + bw) as usize;
+                    for (_, _, color) in samples {
+                        buffer.colors[i] += color;
+                        buffer.weights[i] += 1.0;
+                    }
+                    continue;
+                }
+                for (offset_x, offset_y, color) in samples {
+                    // position of the sample relative to the upper left corner of the pixel
+                    // (the offsets point upwards, the rows of the image downwards)
+                    let (sx, sy) = (offset_x, 1.0 - offset_y);
+                    for dy in -(margin as i32)..margin as i32 + 1 {
+                        for dx in -(margin as i32)..margin as i32 + 1 {
+                            let weight = self
+                                .filter
+                                .weight(sx - (dx as f32 + 0.5), sy - (dy as f32 + 0.5));
+                            if weight == 0.0 {
+                                continue;
+                            }
+                            let i =
+                                ((bh as i32 + dy) * buffer_width as i32 + bw as i32 + dx) as usize;
+                            buffer.colors[i] += color * weight;
+                            buffer.weights[i] += weight;
+                        }
+                    }
+                }
+            }
+        }
+        return buffer;
     }
 
-    fn render_image_rgbimage(&mut self, from: vec3, to: vec3) -> image::RgbImage {
-        let mut img = image::RgbImage::new(self.width, self.height);
+    /// Renders the image tile by tile on the thread pool and returns the linear (unclamped) colors
+    /// of the pixels and the number of samples spent on them in row-major order. The buffers of
+    /// the tiles are merged in a fixed order, so the result does not depend on the number of
+    /// threads.
+    fn render_pixels(&self, camera: &Camera, tqdm_desc: Option<&str>) -> (Vec<vec3>, Vec<u32>) {
+        let margin = if self.filter.is_pixel_box() {
+            0
+        } else {
+            f32::ceil(self.filter.radius) as u32
+        };
 
-        let dir_z = -(self.height as f32) / (2.0 * f32::tan(self.fov / 2.0));
-        for h in tqdm::tqdm(0..self.height) {
-            for w in 0..self.width {
-                let color = self.calc_color_at_pixel(w, h, dir_z, from, to);
-                img.put_pixel(w, h, image::Rgb(color));
+        let mut tiles: Vec<Tile> = Vec::new();
+        for y0 in (0..self.height).step_by(TILE_SIZE as usize) {
+            for x0 in (0..self.width).step_by(TILE_SIZE as usize) {
+                tiles.push(Tile {
+                    x0: x0,
+                    y0: y0,
+                    x1: u32::min(x0 + TILE_SIZE, self.width),
+                    y1: u32::min(y0 + TILE_SIZE, self.height),
+                });
+            }
+        }
+
+        let mut buffers: Vec<Option<TileBuffer>> = (0..tiles.len()).map(|_| None).collect();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.pool.in_place_scope(|scope| {
+            for (i, tile) in tiles.iter().enumerate() {
+                let sender = sender.clone();
+                scope.spawn(move |_| {
+                    let buffer = self.render_tile(*tile, margin, camera);
+                    sender.send((i, buffer)).unwrap();
+                });
+            }
+
+            let finished_tiles = (0..tiles.len()).map(|_| receiver.recv().unwrap());
+            for (i, buffer) in tqdm::tqdm(finished_tiles).desc(tqdm_desc) {
+                buffers[i] = Some(buffer);
+            }
+        });
+
+        let num_pixels = (self.width * self.height) as usize;
+        let mut colors: Vec<vec3> = vec![
+            vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            num_pixels
+        ];
+        let mut weights: Vec<f32> = vec![0.0; num_pixels];
+        let mut sample_sums: Vec<vec3> = colors.clone();
+        let mut sample_counts: Vec<u32> = vec![0; num_pixels];
+        for (tile, buffer) in tiles.iter().zip(buffers) {
+            let buffer = buffer.unwrap();
+            let tile_width = tile.x1 - tile.x0;
+            let buffer_width = tile_width + 2 * margin;
+            for (i, color) in buffer.colors.into_iter().enumerate() {
+                let w = (tile.x0 + i as u32 % buffer_width) as i32 - margin as i32;
+                let h = (tile.y0 + i as u32 / buffer_width) as i32 - margin as i32;
+                if w < 0 || h < 0 || w >= self.width as i32 || h >= self.height as i32 {
+                    continue;
+                }
+                let j = h as usize * self.width as usize + w as usize;
+                colors[j] += color;
+                weights[j] += buffer.weights[i];
+            }
+            for (i, count) in buffer.sample_counts.into_iter().enumerate() {
+                let w = tile.x0 + i as u32 % tile_width;
+                let h = tile.y0 + i as u32 / tile_width;
+                sample_sums[(h * self.width + w) as usize] = buffer.sample_sums[i];
+                sample_counts[(h * self.width + w) as usize] = count;
             }
         }
+
+        let pixels = (0..num_pixels)
+            .map(|j| {
+                if weights[j] >= MIN_FILTER_WEIGHT {
+                    colors[j] / weights[j]
+                } else if sample_counts[j] > 0 {
+                    sample_sums[j] / sample_counts[j] as f32
+                } else {
+                    vec3 {
+                        x: 0.0,
+                        y: 0.0,
+                        z: 0.0,
+                    }
+                }
+            })
+            .collect();
+        return (pixels, sample_counts);
+    }
+
+    fn framebuffer_to_rgbimage(
+        &self,
+        framebuffer: Vec<vec3>,
+        (width, height): (u32, u32),
+    ) -> RgbImage {
+        let mut img = RgbImage::new(width, height);
+
+        for (i, color) in framebuffer.into_iter().enumerate() {
+            let w = i as u32 % width;
+            let h = i as u32 / width;
+            img.put_pixel(w, h, image::Rgb(self.to_rgb8(color)));
+        }
         return img;
     }
 
-    fn render_image_raw(&mut self, from: vec3, to: vec3, tqdm_desc: &str) -> Vec<u8> {
+    fn render_image_raw(&self, camera: &Camera, tqdm_desc: &str) -> Vec<u8> {
         let mut pixels: Vec<u8> = Vec::new();
 
-        let dir_z = -(self.height as f32) / (2.0 * f32::tan(self.fov / 2.0));
-        for h in tqdm::tqdm(0..self.height).desc(Some(tqdm_desc)) {
-            for w in 0..self.width {
-                let color = self.calc_color_at_pixel(w, h, dir_z, from, to);
-                pixels.push(color[0]);
-                pixels.push(color[1]);
-                pixels.push(color[2]);
-            }
+        for color in self.render_pixels(camera, Some(tqdm_desc)).0 {
+            let color = self.to_rgb8(color);
+            pixels.push(color[0]);
+            pixels.push(color[1]);
+            pixels.push(color[2]);
         }
         return pixels;
     }
 
+    /// Moves the camera on a circle around its target and renders a gif of the images. The
+    /// aperture and focus distance of the lens change linearly from those of `camera` in the
+    /// first frame to `lens_end` in the last frame.
+    #[allow(clippy::too_many_arguments)]
     pub fn rotate_cam_around_point_and_render_images(
-        &mut self,
-        look_at_point: (f32, f32, f32),
-        y_level: i32,
+        &self,
+        camera: &Camera,
+        y_level: f32,
         radius: f32,
         num_of_images: u32,
+        lens_end: (f32, f32),
         path: &str,
         versionize: bool,
     ) {
@@ -364,11 +1079,7 @@ impl Raytracer {
         let end = 2.0 * std::f32::consts::PI - 2.0 * std::f32::consts::PI / num_of_images as f32
             + std::f32::consts::FRAC_PI_2;
         let range = itertools_num::linspace(start, end, num_of_images as usize);
-        let look_at = vec3 {
-            x: look_at_point.0,
-            y: look_at_point.1,
-            z: look_at_point.2,
-        };
+        let look_at = camera.target;
 
         let mut frames: Vec<gif::Frame> = Vec::new();
         let len_of_range = range.len().to_string().len();
@@ -376,13 +1087,22 @@ impl Raytracer {
         for (i, e) in range.clone().enumerate() {
             let x = f32::cos(e) * radius + look_at.x;
             let z = f32::sin(e) * radius + look_at.z;
-            let img = self.render_image_raw(
-                vec3 {
+            let t = if num_of_images > 1 {
+                i as f32 / (num_of_images - 1) as f32
+            } else {
+                0.0
+            };
+            let aperture = camera.aperture + (lens_end.0 - camera.aperture) * t;
+            let focus_distance = camera.focus() + (lens_end.1 - camera.focus()) * t;
+            let frame_camera = camera
+                .with_eye(vec3 {
                     x: x,
-                    y: y_level as f32,
+                    y: y_level,
                     z: z,
-                },
-                look_at,
+                })
+                .with_lens(aperture, Some(focus_distance));
+            let img = self.render_image_raw(
+                &frame_camera,
                 format!(
                     "Calculating image {:0>len$} of {:0>len$}",
                     i + 1,
@@ -0,0 +1,779 @@
+use crate::raytracer::Integrator;
+use crate::setup;
+use crate::utils::background::BackgroundMode;
+use crate::utils::camera::Projection;
+use crate::utils::filter::FilterKind;
+use crate::utils::light::{Attenuation, Light};
+use crate::utils::material::Material;
+use crate::utils::mesh::Mesh;
+use crate::utils::procedural::{Pattern, ProceduralTexture, TextureSpace};
+use crate::utils::sampler::SamplePattern;
+use crate::utils::sphere::Sphere;
+use crate::utils::texture::{ImageTexture, Texture, TextureFilter, TextureWrap};
+use crate::utils::tonemap::{Gamma, ToneMapping};
+use crate::utils::vec3::vec3;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A scene loaded from a TOML scene-description file.
+///
+/// Every setting is optional. Settings that are missing in the file fall back to the
+/// command line arguments (or their default values).
+pub struct Scene {
+    pub spheres: Vec<Sphere>,
+    pub meshes: Vec<Mesh>,
+    pub lights: Vec<Light>,
+    pub background_mode: Option<BackgroundMode>,
+    pub background_color: Option<(u32, u32, u32)>,
+    pub background_top: Option<(u32, u32, u32)>,
+    pub background_bottom: Option<(u32, u32, u32)>,
+    /// Path of the backplate image (relative to the working directory)
+    pub backplate: Option<String>,
+    /// Path of the environment map (relative to the working directory)
+    pub environment: Option<String>,
+    pub environment_rotation: Option<f32>,
+    pub environment_intensity: Option<f32>,
+    pub sky: Option<bool>,
+    pub sun_elevation: Option<f32>,
+    pub sun_azimuth: Option<f32>,
+    pub turbidity: Option<f32>,
+    pub sun_intensity: Option<f32>,
+    pub sky_intensity: Option<f32>,
+    pub floor_enabled: Option<bool>,
+    pub floor_center: Option<(f32, f32, f32)>,
+    pub floor_size: Option<(f32, f32)>,
+    pub floor_normal: Option<(f32, f32, f32)>,
+    pub floor_color: Option<(u32, u32, u32)>,
+    pub floor_checker_color: Option<(u32, u32, u32)>,
+    pub floor_tile_size: Option<f32>,
+    /// Path of the floor texture (relative to the working directory)
+    pub floor_texture: Option<String>,
+    pub floor_texture_scale: Option<(f32, f32)>,
+    /// Procedural texture of the floor, if the texture names one of the `[textures]`
+    pub floor_pattern: Option<Arc<Texture>>,
+    pub max_depth: Option<u32>,
+    pub occlusion_offset: Option<f32>,
+    pub anti_aliasing: Option<u32>,
+    pub sampler: Option<SamplePattern>,
+    pub samples: Option<u32>,
+    pub adaptive: Option<bool>,
+    pub min_samples: Option<u32>,
+    pub adaptive_threshold: Option<f32>,
+    pub seed: Option<u64>,
+    pub filter: Option<FilterKind>,
+    pub filter_radius: Option<f32>,
+    pub integrator: Option<Integrator>,
+    pub path_samples: Option<u32>,
+    pub shadow_samples: Option<u32>,
+    pub tone_mapping: Option<ToneMapping>,
+    pub exposure: Option<f32>,
+    pub gamma: Option<Gamma>,
+    pub fov: Option<f32>,
+    pub camera_pos: Option<(f32, f32, f32)>,
+    pub look_at_pos: Option<(f32, f32, f32)>,
+    pub up: Option<(f32, f32, f32)>,
+    pub roll: Option<f32>,
+    pub aperture: Option<f32>,
+    pub focus_distance: Option<f32>,
+    pub projection: Option<Projection>,
+    pub view_width: Option<f32>,
+    pub fisheye_fov: Option<f32>,
+}
+
+#[derive(Debug)]
+pub struct SceneError {
+    pub path: String,
+    pub line: Option<usize>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ": key '{}'", key)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    render: Option<RenderSection>,
+    camera: Option<CameraSection>,
+    background: Option<BackgroundSection>,
+    floor: Option<FloorSection>,
+    #[serde(default)]
+    textures: HashMap<String, TextureSection>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialSection>,
+    #[serde(default)]
+    spheres: Vec<SphereSection>,
+    #[serde(default)]
+    meshes: Vec<MeshSection>,
+    #[serde(default)]
+    lights: Vec<LightSection>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenderSection {
+    max_depth: Option<u32>,
+    occlusion_offset: Option<f32>,
+    #[serde(default, deserialize_with = "at_least_one")]
+    anti_aliasing: Option<u32>,
+    sampler: Option<SamplePattern>,
+    #[serde(default, deserialize_with = "at_least_one")]
+    samples: Option<u32>,
+    adaptive: Option<bool>,
+    #[serde(default, deserialize_with = "at_least_one")]
+    min_samples: Option<u32>,
+    adaptive_threshold: Option<f32>,
+    seed: Option<u64>,
+    filter: Option<FilterKind>,
+    filter_radius: Option<f32>,
+    integrator: Option<Integrator>,
+    #[serde(default, deserialize_with = "at_least_one")]
+    path_samples: Option<u32>,
+    #[serde(default, deserialize_with = "at_least_one")]
+    shadow_samples: Option<u32>,
+    tone_mapping: Option<ToneMapping>,
+    exposure: Option<f32>,
+    gamma: Option<Gamma>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraSection {
+    position: Option<[f32; 3]>,
+    look_at: Option<[f32; 3]>,
+    /// Vertical field of view in degrees
+    fov: Option<f32>,
+    up: Option<[f32; 3]>,
+    roll: Option<f32>,
+    aperture: Option<f32>,
+    focus_distance: Option<f32>,
+    projection: Option<Projection>,
+    /// Width of the visible area of the orthographic projection
+    view_width: Option<f32>,
+    /// Field of view of the fisheye projection in degrees
+    fisheye_fov: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackgroundSection {
+    mode: Option<BackgroundMode>,
+    #[serde(default, deserialize_with = "rgb_color")]
+    color: Option<[u32; 3]>,
+    /// Colors of the gradient straight up and straight down
+    #[serde(default, deserialize_with = "rgb_color")]
+    top: Option<[u32; 3]>,
+    #[serde(default, deserialize_with = "rgb_color")]
+    bottom: Option<[u32; 3]>,
+    /// Image shown behind the objects (relative to the scene file)
+    backplate: Option<String>,
+    /// Equirectangular image (relative to the scene file)
+    environment: Option<String>,
+    /// Rotation of the environment map around the y-axis in degrees
+    environment_rotation: Option<f32>,
+    environment_intensity: Option<f32>,
+    /// Analytic daylight sky with a sun light instead of the gradient
+    sky: Option<bool>,
+    /// Angle of the sun above the horizon in degrees
+    sun_elevation: Option<f32>,
+    /// Direction of the sun in degrees, measured from -z towards +x
+    sun_azimuth: Option<f32>,
+    turbidity: Option<f32>,
+    sun_intensity: Option<f32>,
+    sky_intensity: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FloorSection {
+    enabled: Option<bool>,
+    center: Option<[f32; 3]>,
+    size: Option<[f32; 2]>,
+    normal: Option<[f32; 3]>,
+    #[serde(default, deserialize_with = "rgb_color")]
+    color: Option<[u32; 3]>,
+    #[serde(default, deserialize_with = "rgb_color")]
+    checker_color: Option<[u32; 3]>,
+    tile_size: Option<f32>,
+    /// Name of a procedural texture or image (relative to the scene file) replacing the color
+    texture: Option<String>,
+    /// Number of repetitions of the texture along the width and the depth
+    texture_scale: Option<[f32; 2]>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialSection {
+    refractive_index: f32,
+    diffuse_multiplier: f32,
+    specular_multiplier: f32,
+    reflection_multiplier: f32,
+    refraction_multiplier: f32,
+    color: [f32; 3],
+    specular_exponent: f32,
+    #[serde(default)]
+    emission: [f32; 3],
+    #[serde(default)]
+    fresnel: bool,
+    /// Names of procedural textures or images (relative to the scene file) replacing the color
+    /// and scaling the multipliers
+    color_texture: Option<toml::Spanned<String>>,
+    diffuse_texture: Option<toml::Spanned<String>>,
+    specular_texture: Option<toml::Spanned<String>>,
+    reflection_texture: Option<toml::Spanned<String>>,
+    refraction_texture: Option<toml::Spanned<String>>,
+    /// Settings of the images
+    texture_wrap: Option<TextureWrap>,
+    texture_filter: Option<TextureFilter>,
+    /// Number of repetitions of the images along u and v
+    texture_scale: Option<[f32; 2]>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureSection {
+    pattern: PatternName,
+    /// Colors for the values 0 and 1 of the pattern
+    colors: Option<[[f32; 3]; 2]>,
+    /// Frequency of the pattern
+    scale: Option<f32>,
+    space: Option<TextureSpace>,
+    /// Layers of noise of turbulence and marble
+    octaves: Option<u32>,
+    /// Strength of the distortion of marble and wood
+    distortion: Option<f32>,
+    /// Direction of stripes
+    #[serde(default, deserialize_with = "nonzero_direction")]
+    direction: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PatternName {
+    Noise,
+    Turbulence,
+    Marble,
+    Wood,
+    Checker,
+    Stripes,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereSection {
+    center: [f32; 3],
+    radius: f32,
+    material: toml::Spanned<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshSection {
+    /// Path to a Wavefront OBJ file, relative to the scene file
+    file: toml::Spanned<String>,
+    material: toml::Spanned<String>,
+    #[serde(default)]
+    position: [f32; 3],
+    #[serde(default = "default_scale")]
+    scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// Deserializes an optional count that has to be at least 1
+fn at_least_one<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    let value = Option::<u32>::deserialize(deserializer)?;
+    if value == Some(0) {
+        return Err(serde::de::Error::custom("must be at least 1"));
+    }
+    return Ok(value);
+}
+
+/// Deserializes an optional direction, which must not be the zero vector
+fn nonzero_direction<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<[f32; 3]>, D::Error> {
+    let value = Option::<[f32; 3]>::deserialize(deserializer)?;
+    if value == Some([0.0, 0.0, 0.0]) {
+        return Err(serde::de::Error::custom("must not be the zero vector"));
+    }
+    return Ok(value);
+}
+
+/// Deserializes an optional RGB color with components between 0 and 255
+fn rgb_color<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<[u32; 3]>, D::Error> {
+    let value = Option::<[u32; 3]>::deserialize(deserializer)?;
+    if value.is_some_and(|color| color.iter().any(|c| *c > 255)) {
+        return Err(serde::de::Error::custom(
+            "color components must be between 0 and 255",
+        ));
+    }
+    return Ok(value);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightSection {
+    /// Ignored for directional lights
+    #[serde(default)]
+    position: [f32; 3],
+    intensity: f32,
+    /// RGB color (components may be greater than 1)
+    color: Option<[f32; 3]>,
+    #[serde(default = "default_light_shape")]
+    shape: LightShapeName,
+    /// Radius of sphere and disk lights
+    radius: Option<f32>,
+    /// Facing direction of rectangle and disk lights
+    normal: Option<[f32; 3]>,
+    /// Extent (width, depth) of rectangle lights
+    size: Option<[f32; 2]>,
+    /// Direction the light shines in (directional and spot lights)
+    direction: Option<[f32; 3]>,
+    /// Angle between the axis and the edge of the cone of spot lights in degrees
+    angle: Option<f32>,
+    /// Width of the soft edge of spot lights in degrees
+    falloff: Option<f32>,
+    #[serde(default = "default_attenuation")]
+    attenuation: AttenuationName,
+    /// Coefficients of the polynomial attenuation
+    constant: Option<f32>,
+    linear: Option<f32>,
+    quadratic: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum AttenuationName {
+    None,
+    InverseSquare,
+    Polynomial,
+}
+
+fn default_attenuation() -> AttenuationName {
+    AttenuationName::None
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LightShapeName {
+    Point,
+    Sphere,
+    Rectangle,
+    Disk,
+    Directional,
+    Spot,
+}
+
+fn default_light_shape() -> LightShapeName {
+    LightShapeName::Point
+}
+
+fn to_vec3(v: [f32; 3]) -> vec3 {
+    vec3 {
+        x: v[0],
+        y: v[1],
+        z: v[2],
+    }
+}
+
+fn line_of_offset(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+impl Scene {
+    pub fn from_file(path: &str) -> Result<Scene, SceneError> {
+        let source = std::fs::read_to_string(path).map_err(|e| SceneError {
+            path: path.to_string(),
+            line: None,
+            key: None,
+            message: e.to_string(),
+        })?;
+        return Scene::parse(path, &source);
+    }
+
+    pub fn parse(path: &str, source: &str) -> Result<Scene, SceneError> {
+        let deserializer = toml::Deserializer::new(source);
+        let file: SceneFile = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let key = e.path().to_string();
+            let inner = e.into_inner();
+            SceneError {
+                path: path.to_string(),
+                line: inner.span().map(|span| line_of_offset(source, span.start)),
+                key: if key == "." { None } else { Some(key) },
+                message: inner.message().to_string(),
+            }
+        })?;
+
+        let scene_dir = std::path::Path::new(path)
+            .parent()
+            .unwrap_or(std::path::Path::new(""));
+        let textures: HashMap<String, Arc<Texture>> = file
+            .textures
+            .iter()
+            .map(|(name, t)| {
+                let pattern = match t.pattern {
+                    PatternName::Noise => Pattern::Noise,
+                    PatternName::Turbulence => Pattern::Turbulence {
+                        octaves: t.octaves.unwrap_or(7),
+                    },
+                    PatternName::Marble => Pattern::Marble {
+                        octaves: t.octaves.unwrap_or(7),
+                        distortion: t.distortion.unwrap_or(5.0),
+                    },
+                    PatternName::Wood => Pattern::Wood {
+                        distortion: t.distortion.unwrap_or(0.5),
+                    },
+                    PatternName::Checker => Pattern::Checker,
+                    PatternName::Stripes => Pattern::Stripes {
+                        direction: to_vec3(t.direction.unwrap_or([1.0, 0.0, 0.0])),
+                    },
+                };
+                let mut texture = ProceduralTexture::new(pattern)
+                    .with_scale(t.scale.unwrap_or(1.0))
+                    .with_space(t.space.unwrap_or(TextureSpace::Object));
+                if let Some(colors) = t.colors {
+                    texture = texture.with_colors((to_vec3(colors[0]), to_vec3(colors[1])));
+                }
+                (name.clone(), Arc::new(Texture::Procedural(texture)))
+            })
+            .collect();
+
+        let mut materials = setup::get_materials();
+        for (name, m) in file.materials {
+            let load_texture =
+                |file: &Option<toml::Spanned<String>>, key: &str, linearize: bool| {
+                    let Some(file) = file else {
+                        return Ok(None);
+                    };
+                    if let Some(texture) = textures.get(file.get_ref()) {
+                        return Ok(Some(texture.clone()));
+                    }
+                    let texture_path = scene_dir.join(file.get_ref());
+                    let texture =
+                        ImageTexture::load(&texture_path, linearize).map_err(|e| SceneError {
+                            path: path.to_string(),
+                            line: Some(line_of_offset(source, file.span().start)),
+                            key: Some(format!("materials.{}.{}", name, key)),
+                            message: format!("failed to load '{}': {}", texture_path.display(), e),
+                        })?;
+                    let texture = texture
+                        .with_wrap(m.texture_wrap.unwrap_or(TextureWrap::Wrap))
+                        .with_filter(m.texture_filter.unwrap_or(TextureFilter::Bilinear))
+                        .with_scale(m.texture_scale.map_or((1.0, 1.0), |s| (s[0], s[1])));
+                    Ok(Some(Arc::new(Texture::Image(texture))))
+                };
+            let color_texture = load_texture(&m.color_texture, "color_texture", true)?;
+            let diffuse_texture = load_texture(&m.diffuse_texture, "diffuse_texture", false)?;
+            let specular_texture = load_texture(&m.specular_texture, "specular_texture", false)?;
+            let reflection_texture =
+                load_texture(&m.reflection_texture, "reflection_texture", false)?;
+            let refraction_texture =
+                load_texture(&m.refraction_texture, "refraction_texture", false)?;
+
+            let mut material = Material::new(
+                m.refractive_index,
+                m.diffuse_multiplier,
+                m.specular_multiplier,
+                m.reflection_multiplier,
+                m.refraction_multiplier,
+                to_vec3(m.color),
+                m.specular_exponent,
+            )
+            .with_emission(to_vec3(m.emission));
+            if m.fresnel {
+                material = material.with_fresnel();
+            }
+            if let Some(texture) = color_texture {
+                material = material.with_color_texture(texture);
+            }
+            if let Some(texture) = diffuse_texture {
+                material = material.with_diffuse_texture(texture);
+            }
+            if let Some(texture) = specular_texture {
+                material = material.with_specular_texture(texture);
+            }
+            if let Some(texture) = reflection_texture {
+                material = material.with_reflection_texture(texture);
+            }
+            if let Some(texture) = refraction_texture {
+                material = material.with_refraction_texture(texture);
+            }
+            materials.insert(name, material);
+        }
+
+        let lookup_material =
+            |name: &toml::Spanned<String>, key: String| match materials.get(name.get_ref()) {
+                Some(material) => Ok(material.clone()),
+                None => Err(SceneError {
+                    path: path.to_string(),
+                    line: Some(line_of_offset(source, name.span().start)),
+                    key: Some(key),
+                    message: format!("unknown material '{}'", name.get_ref()),
+                }),
+            };
+
+        let mut spheres: Vec<Sphere> = Vec::new();
+        for (i, s) in file.spheres.iter().enumerate() {
+            let material = lookup_material(&s.material, format!("spheres[{}].material", i))?;
+            spheres.push(Sphere::new(to_vec3(s.center), s.radius, material));
+        }
+
+        let mut meshes: Vec<Mesh> = Vec::new();
+        for (i, m) in file.meshes.iter().enumerate() {
+            let material = lookup_material(&m.material, format!("meshes[{}].material", i))?;
+            let obj_path = scene_dir.join(m.file.get_ref());
+            let mesh =
+                Mesh::load_obj(&obj_path, material, to_vec3(m.position), m.scale).map_err(|e| {
+                    SceneError {
+                        path: path.to_string(),
+                        line: Some(line_of_offset(source, m.file.span().start)),
+                        key: Some(format!("meshes[{}].file", i)),
+                        message: format!("failed to load '{}': {}", obj_path.display(), e),
+                    }
+                })?;
+            meshes.push(mesh);
+        }
+
+        let lights: Vec<Light> = file
+            .lights
+            .iter()
+            .map(|l| {
+                let light = Light::new(to_vec3(l.position), l.intensity)
+                    .with_color(to_vec3(l.color.unwrap_or([1.0, 1.0, 1.0])))
+                    .with_attenuation(match l.attenuation {
+                        AttenuationName::None => Attenuation::None,
+                        AttenuationName::InverseSquare => Attenuation::InverseSquare,
+                        AttenuationName::Polynomial => Attenuation::Polynomial {
+                            constant: l.constant.unwrap_or(1.0),
+                            linear: l.linear.unwrap_or(0.0),
+                            quadratic: l.quadratic.unwrap_or(0.0),
+                        },
+                    });
+                let radius = l.radius.unwrap_or(1.0);
+                let normal = to_vec3(l.normal.unwrap_or([0.0, -1.0, 0.0]));
+                let direction = to_vec3(l.direction.unwrap_or([0.0, -1.0, 0.0]));
+                match l.shape {
+                    LightShapeName::Point => light,
+                    LightShapeName::Sphere => light.with_sphere(radius),
+                    LightShapeName::Rectangle => {
+                        let size = l.size.unwrap_or([2.0, 2.0]);
+                        light.with_rectangle(normal, (size[0], size[1]))
+                    }
+                    LightShapeName::Disk => light.with_disk(normal, radius),
+                    LightShapeName::Directional => light.with_directional(direction),
+                    LightShapeName::Spot => light.with_spot(
+                        direction,
+                        l.angle.unwrap_or(30.0),
+                        l.falloff.unwrap_or(5.0),
+                    ),
+                }
+            })
+            .collect();
+
+        let render = file.render;
+        let camera = file.camera;
+        let floor = file.floor;
+        let background = file.background;
+        return Ok(Scene {
+            spheres: spheres,
+            meshes: meshes,
+            lights: lights,
+            background_mode: background.as_ref().and_then(|b| b.mode),
+            background_color: background
+                .as_ref()
+                .and_then(|b| b.color)
+                .map(|c| (c[0], c[1], c[2])),
+            background_top: background
+                .as_ref()
+                .and_then(|b| b.top)
+                .map(|c| (c[0], c[1], c[2])),
+            background_bottom: background
+                .as_ref()
+                .and_then(|b| b.bottom)
+                .map(|c| (c[0], c[1], c[2])),
+            backplate: background
+                .as_ref()
+                .and_then(|b| b.backplate.as_ref())
+                .map(|b| scene_dir.join(b).to_string_lossy().into_owned()),
+            environment: background
+                .as_ref()
+                .and_then(|b| b.environment.as_ref())
+                .map(|e| scene_dir.join(e).to_string_lossy().into_owned()),
+            environment_rotation: background.as_ref().and_then(|b| b.environment_rotation),
+            environment_intensity: background.as_ref().and_then(|b| b.environment_intensity),
+            sky: background.as_ref().and_then(|b| b.sky),
+            sun_elevation: background.as_ref().and_then(|b| b.sun_elevation),
+            sun_azimuth: background.as_ref().and_then(|b| b.sun_azimuth),
+            turbidity: background.as_ref().and_then(|b| b.turbidity),
+            sun_intensity: background.as_ref().and_then(|b| b.sun_intensity),
+            sky_intensity: background.as_ref().and_then(|b| b.sky_intensity),
+            floor_enabled: floor.as_ref().and_then(|f| f.enabled),
+            floor_center: floor
+                .as_ref()
+                .and_then(|f| f.center)
+                .map(|c| (c[0], c[1], c[2])),
+            floor_size: floor.as_ref().and_then(|f| f.size).map(|s| (s[0], s[1])),
+            floor_normal: floor
+                .as_ref()
+                .and_then(|f| f.normal)
+                .map(|n| (n[0], n[1], n[2])),
+            floor_color: floor
+                .as_ref()
+                .and_then(|f| f.color)
+                .map(|c| (c[0], c[1], c[2])),
+            floor_checker_color: floor
+                .as_ref()
+                .and_then(|f| f.checker_color)
+                .map(|c| (c[0], c[1], c[2])),
+            floor_tile_size: floor.as_ref().and_then(|f| f.tile_size),
+            floor_texture: floor
+                .as_ref()
+                .and_then(|f| f.texture.as_ref())
+                .filter(|t| !textures.contains_key(*t))
+                .map(|t| scene_dir.join(t).to_string_lossy().into_owned()),
+            floor_pattern: floor
+                .as_ref()
+                .and_then(|f| f.texture.as_ref())
+                .and_then(|t| textures.get(t).cloned()),
+            floor_texture_scale: floor
+                .as_ref()
+                .and_then(|f| f.texture_scale)
+                .map(|s| (s[0], s[1])),
+            max_depth: render.as_ref().and_then(|r| r.max_depth),
+            occlusion_offset: render.as_ref().and_then(|r| r.occlusion_offset),
+            anti_aliasing: render.as_ref().and_then(|r| r.anti_aliasing),
+            sampler: render.as_ref().and_then(|r| r.sampler),
+            samples: render.as_ref().and_then(|r| r.samples),
+            adaptive: render.as_ref().and_then(|r| r.adaptive),
+            min_samples: render.as_ref().and_then(|r| r.min_samples),
+            adaptive_threshold: render.as_ref().and_then(|r| r.adaptive_threshold),
+            seed: render.as_ref().and_then(|r| r.seed),
+            filter: render.as_ref().and_then(|r| r.filter),
+            filter_radius: render.as_ref().and_then(|r| r.filter_radius),
+            integrator: render.as_ref().and_then(|r| r.integrator),
+            path_samples: render.as_ref().and_then(|r| r.path_samples),
+            shadow_samples: render.as_ref().and_then(|r| r.shadow_samples),
+            tone_mapping: render.as_ref().and_then(|r| r.tone_mapping),
+            exposure: render.as_ref().and_then(|r| r.exposure),
+            gamma: render.as_ref().and_then(|r| r.gamma),
+            fov: camera.as_ref().and_then(|c| c.fov),
+            camera_pos: camera
+                .as_ref()
+                .and_then(|c| c.position)
+                .map(|p| (p[0], p[1], p[2])),
+            look_at_pos: camera
+                .as_ref()
+                .and_then(|c| c.look_at)
+                .map(|p| (p[0], p[1], p[2])),
+            up: camera
+                .as_ref()
+                .and_then(|c| c.up)
+                .map(|u| (u[0], u[1], u[2])),
+            roll: camera.as_ref().and_then(|c| c.roll),
+            aperture: camera.as_ref().and_then(|c| c.aperture),
+            focus_distance: camera.as_ref().and_then(|c| c.focus_distance),
+            projection: camera.as_ref().and_then(|c| c.projection),
+            view_width: camera.as_ref().and_then(|c| c.view_width),
+            fisheye_fov: camera.as_ref().and_then(|c| c.fisheye_fov),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(source: &str) -> SceneError {
+        match Scene::parse("test.toml", source) {
+            Ok(_) => panic!("expected an error for:\n{}", source),
+            Err(e) => e,
+        }
+    }
+
+    fn assert_error(source: &str, line: usize, key: &str) {
+        let error = parse_error(source);
+        assert_eq!(error.line, Some(line), "{}", error);
+        assert_eq!(error.key.as_deref(), Some(key), "{}", error);
+    }
+
+    #[test]
+    fn valid_scene_parses() {
+        assert!(Scene::parse("test.toml", "[render]\nsamples = 4\n").is_ok());
+    }
+
+    #[test]
+    fn zero_samples() {
+        assert_error(
+            "[render]\nmax_depth = 4\nsamples = 0\n",
+            3,
+            "render.samples",
+        );
+    }
+
+    #[test]
+    fn wrong_type() {
+        assert_error("[camera]\nfov = \"wide\"\n", 2, "camera.fov");
+    }
+
+    #[test]
+    fn color_above_255() {
+        assert_error(
+            "\n[background]\ncolor = [53, 300, 160]\n",
+            3,
+            "background.color",
+        );
+        assert_error(
+            "[floor]\ncolor = [230, 102, 30]\nchecker_color = [256, 0, 0]\n",
+            3,
+            "floor.checker_color",
+        );
+    }
+
+    #[test]
+    fn unknown_material() {
+        let source = "[[spheres]]\n\
+                      center = [0.0, 0.0, -20.0]\n\
+                      radius = 2.0\n\
+                      material = \"mirror\"\n\
+                      \n\
+                      [[spheres]]\n\
+                      center = [3.0, 0.0, -20.0]\n\
+                      radius = 1.0\n\
+                      material = \"velvet\"\n";
+        assert_error(source, 9, "spheres[1].material");
+    }
+
+    #[test]
+    fn error_message_names_file_line_and_key() {
+        let error = parse_error("[render]\nsamples = 0\n");
+        assert!(
+            error
+                .to_string()
+                .starts_with("test.toml:2: key 'render.samples': "),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use crate::utils::{light::Light};
-use crate::utils::vec3::vec3;
-use crate::utils::sphere::Sphere;
+use crate::utils::light::Light;
 use crate::utils::material::Material;
+use crate::utils::plane::{Checker, Plane};
+use crate::utils::procedural::{Pattern, ProceduralTexture, TextureSpace};
+use crate::utils::sphere::Sphere;
+use crate::utils::texture::Texture;
+use crate::utils::vec3::vec3;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub fn get_materials() -> HashMap<String, Material> {
     let mut materials: HashMap<String, Material> = HashMap::new();
@@ -38,6 +42,23 @@ pub fn get_materials() -> HashMap<String, Material> {
             125.0,
         ),
     );
+    materials.insert(
+        "crystal".to_string(),
+        Material::new(
+            1.5,
+            0.0,
+            0.9,
+            0.1,
+            0.9,
+            vec3 {
+                x: 0.9,
+                y: 0.9,
+                z: 0.9,
+            },
+            125.0,
+        )
+        .with_fresnel(),
+    );
     materials.insert(
         "rubber".to_string(),
         Material::new(
@@ -102,9 +123,152 @@ pub fn get_materials() -> HashMap<String, Material> {
             50.0,
         ),
     );
+    materials.insert(
+        "marble".to_string(),
+        Material::new(
+            1.0,
+            0.9,
+            0.5,
+            0.1,
+            0.0,
+            vec3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            50.0,
+        )
+        .with_color_texture(Arc::new(Texture::Procedural(
+            ProceduralTexture::new(Pattern::Marble {
+                octaves: 7,
+                distortion: 5.0,
+            })
+            .with_colors((
+                vec3 {
+                    x: 0.95,
+                    y: 0.93,
+                    z: 0.9,
+                },
+                vec3 {
+                    x: 0.25,
+                    y: 0.25,
+                    z: 0.3,
+                },
+            ))
+            .with_scale(1.5),
+        ))),
+    );
+    materials.insert(
+        "wood".to_string(),
+        Material::new(
+            1.0,
+            0.9,
+            0.2,
+            0.0,
+            0.0,
+            vec3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            10.0,
+        )
+        .with_color_texture(Arc::new(Texture::Procedural(
+            ProceduralTexture::new(Pattern::Wood { distortion: 0.5 })
+                .with_colors((
+                    vec3 {
+                        x: 0.6,
+                        y: 0.36,
+                        z: 0.16,
+                    },
+                    vec3 {
+                        x: 0.3,
+                        y: 0.15,
+                        z: 0.05,
+                    },
+                ))
+                .with_scale(3.0),
+        ))),
+    );
     return materials;
 }
 
+/// Settings of the floor
+pub struct Floor {
+    pub enabled: bool,
+    pub center: (f32, f32, f32),
+    pub size: (f32, f32),
+    pub normal: (f32, f32, f32),
+    pub color: (u32, u32, u32),
+    /// Second color and tile size of a checkerboard pattern
+    pub checker: Option<((u32, u32, u32), f32)>,
+    /// Image replacing the color
+    pub texture: Option<Arc<Texture>>,
+}
+
+fn color_to_vec3(color: (u32, u32, u32)) -> vec3 {
+    vec3 {
+        x: color.0 as f32,
+        y: color.1 as f32,
+        z: color.2 as f32,
+    } / 255.0
+}
+
+pub fn build_floor(floor: &Floor) -> Plane {
+    let mut material = Material::default();
+    material.color = color_to_vec3(floor.color);
+    material.color_texture = floor.texture.clone();
+    let plane = Plane::from_normal(
+        vec3 {
+            x: floor.center.0,
+            y: floor.center.1,
+            z: floor.center.2,
+        },
+        vec3 {
+            x: floor.normal.0,
+            y: floor.normal.1,
+            z: floor.normal.2,
+        },
+        floor.size,
+        material,
+    );
+    return match floor.checker {
+        Some((color, tile_size)) => plane.with_checker(Checker {
+            color: color_to_vec3(color),
+            tile_size: tile_size,
+        }),
+        None => plane,
+    };
+}
+
+pub fn get_floor(preset: u32) -> Floor {
+    let mut floor = Floor {
+        enabled: true,
+        center: (0.0, -4.0, -30.0),
+        size: (20.0, 40.0),
+        normal: (0.0, 1.0, 0.0),
+        color: (230, 102, 30),
+        checker: None,
+        texture: None,
+    };
+    match preset {
+        // the mirrors only reflect each other and the sky
+        3 => floor.enabled = false,
+        // every tile holds four of the spheres
+        5 => floor.checker = Some(((40, 40, 40), 1.0)),
+        6 => {
+            floor.texture = Some(Arc::new(Texture::Procedural(
+                ProceduralTexture::new(Pattern::Turbulence { octaves: 5 })
+                    .with_colors((color_to_vec3((230, 102, 30)), color_to_vec3((90, 30, 10))))
+                    .with_scale(0.5)
+                    .with_space(TextureSpace::World),
+            )))
+        }
+        _ => {}
+    }
+    return floor;
+}
+
 #[allow(dead_code)]
 pub fn get_spheres_lights_1() -> (Vec<Sphere>, Vec<Light>) {
     let materials = get_materials();
@@ -117,7 +281,7 @@ pub fn get_spheres_lights_1() -> (Vec<Sphere>, Vec<Light>) {
             z: -20.0,
         },
         2.0,
-        materials["mirror"],
+        materials["mirror"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -126,7 +290,7 @@ pub fn get_spheres_lights_1() -> (Vec<Sphere>, Vec<Light>) {
             z: -16.0,
         },
         2.0,
-        materials["sapphire"],
+        materials["sapphire"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -135,7 +299,7 @@ pub fn get_spheres_lights_1() -> (Vec<Sphere>, Vec<Light>) {
             z: -16.0,
         },
         2.0,
-        materials["amber"],
+        materials["amber"].clone(),
     ));
 
     let mut lights: Vec<Light> = Vec::new();
@@ -187,7 +351,7 @@ pub fn get_spheres_lights_2() -> (Vec<Sphere>, Vec<Light>) {
             z: -22.0,
         },
         8.0,
-        materials["sapphire"],
+        materials["sapphire"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -196,7 +360,7 @@ pub fn get_spheres_lights_2() -> (Vec<Sphere>, Vec<Light>) {
             z: -16.0,
         },
         2.0,
-        materials["mirror"],
+        materials["mirror"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -205,7 +369,7 @@ pub fn get_spheres_lights_2() -> (Vec<Sphere>, Vec<Light>) {
             z: -18.0,
         },
         2.0,
-        materials["rubber"],
+        materials["rubber"].clone(),
     ));
 
     let mut lights: Vec<Light> = Vec::new();
@@ -241,7 +405,7 @@ pub fn get_spheres_lights_3() -> (Vec<Sphere>, Vec<Light>) {
             z: -20.0,
         },
         4.0,
-        materials["mirror"],
+        materials["mirror"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -250,7 +414,7 @@ pub fn get_spheres_lights_3() -> (Vec<Sphere>, Vec<Light>) {
             z: -16.0,
         },
         4.0,
-        materials["mirror"],
+        materials["mirror"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -259,7 +423,7 @@ pub fn get_spheres_lights_3() -> (Vec<Sphere>, Vec<Light>) {
             z: -16.0,
         },
         4.0,
-        materials["mirror"],
+        materials["mirror"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -268,7 +432,7 @@ pub fn get_spheres_lights_3() -> (Vec<Sphere>, Vec<Light>) {
             z: -16.0,
         },
         2.0,
-        materials["sapphire"],
+        materials["sapphire"].clone(),
     ));
 
     let mut lights: Vec<Light> = Vec::new();
@@ -312,7 +476,7 @@ pub fn get_spheres_lights_4() -> (Vec<Sphere>, Vec<Light>) {
             z: -20.0,
         },
         4.0,
-        materials["mirror"],
+        materials["mirror"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -321,7 +485,7 @@ pub fn get_spheres_lights_4() -> (Vec<Sphere>, Vec<Light>) {
             z: -20.0,
         },
         3.0,
-        materials["amber"],
+        materials["amber"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -330,7 +494,7 @@ pub fn get_spheres_lights_4() -> (Vec<Sphere>, Vec<Light>) {
             z: -20.0,
         },
         4.0,
-        materials["rubber"],
+        materials["rubber"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -339,7 +503,7 @@ pub fn get_spheres_lights_4() -> (Vec<Sphere>, Vec<Light>) {
             z: -30.0,
         },
         6.0,
-        materials["sapphire"],
+        materials["sapphire"].clone(),
     ));
     spheres.push(Sphere::new(
         vec3 {
@@ -348,7 +512,7 @@ pub fn get_spheres_lights_4() -> (Vec<Sphere>, Vec<Light>) {
             z: -30.0,
         },
         6.0,
-        materials["glass"],
+        materials["glass"].clone(),
     ));
 
     let mut lights: Vec<Light> = Vec::new();
@@ -378,4 +542,171 @@ pub fn get_spheres_lights_4() -> (Vec<Sphere>, Vec<Light>) {
     ));
 
     return (spheres, lights);
-}
\ No newline at end of file
+}
+
+/// Benchmark scene with several thousand small spheres covering the floor
+#[allow(dead_code)]
+pub fn get_spheres_lights_5() -> (Vec<Sphere>, Vec<Light>) {
+    let materials = get_materials();
+    let names = ["mirror", "glass", "rubber", "ivory", "amber", "sapphire"];
+
+    let mut spheres: Vec<Sphere> = Vec::new();
+    for i in 0..39 {
+        for j in 0..79 {
+            spheres.push(Sphere::new(
+                vec3 {
+                    x: -9.5 + i as f32 * 0.5,
+                    y: -3.8,
+                    z: -10.5 - j as f32 * 0.5,
+                },
+                0.2,
+                materials[names[(i + j) % names.len()]].clone(),
+            ));
+        }
+    }
+
+    let mut lights: Vec<Light> = Vec::new();
+    lights.push(Light::new(
+        vec3 {
+            x: 4.0,
+            y: 30.0,
+            z: -20.0,
+        },
+        1.0,
+    ));
+    lights.push(Light::new(
+        vec3 {
+            x: -8.0,
+            y: 10.0,
+            z: -10.0,
+        },
+        0.5,
+    ));
+
+    return (spheres, lights);
+}
+
+/// Spheres with procedural textures
+#[allow(dead_code)]
+pub fn get_spheres_lights_6() -> (Vec<Sphere>, Vec<Light>) {
+    let materials = get_materials();
+    let white = vec3 {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    };
+    let textured = |texture: ProceduralTexture| {
+        Material::new(1.0, 0.9, 0.3, 0.0, 0.0, white, 50.0)
+            .with_color_texture(Arc::new(Texture::Procedural(texture)))
+    };
+
+    let mut spheres: Vec<Sphere> = Vec::new();
+    spheres.push(Sphere::new(
+        vec3 {
+            x: -5.0,
+            y: -1.5,
+            z: -20.0,
+        },
+        2.5,
+        materials["marble"].clone(),
+    ));
+    spheres.push(Sphere::new(
+        vec3 {
+            x: 0.0,
+            y: -1.5,
+            z: -20.0,
+        },
+        2.5,
+        materials["wood"].clone(),
+    ));
+    spheres.push(Sphere::new(
+        vec3 {
+            x: 5.0,
+            y: -1.5,
+            z: -20.0,
+        },
+        2.5,
+        textured(
+            ProceduralTexture::new(Pattern::Checker)
+                .with_colors((
+                    vec3 {
+                        x: 0.1,
+                        y: 0.1,
+                        z: 0.1,
+                    },
+                    white,
+                ))
+                .with_scale(1.2),
+        ),
+    ));
+    spheres.push(Sphere::new(
+        vec3 {
+            x: -2.5,
+            y: -2.5,
+            z: -14.0,
+        },
+        1.5,
+        textured(
+            ProceduralTexture::new(Pattern::Stripes {
+                direction: vec3 {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 0.0,
+                },
+            })
+            .with_colors((
+                white,
+                vec3 {
+                    x: 0.8,
+                    y: 0.1,
+                    z: 0.1,
+                },
+            ))
+            .with_scale(2.0),
+        ),
+    ));
+    spheres.push(Sphere::new(
+        vec3 {
+            x: 2.5,
+            y: -2.5,
+            z: -14.0,
+        },
+        1.5,
+        textured(
+            ProceduralTexture::new(Pattern::Noise)
+                .with_colors((
+                    vec3 {
+                        x: 0.0,
+                        y: 0.2,
+                        z: 0.6,
+                    },
+                    vec3 {
+                        x: 0.6,
+                        y: 0.9,
+                        z: 1.0,
+                    },
+                ))
+                .with_scale(3.0),
+        ),
+    ));
+
+    let mut lights: Vec<Light> = Vec::new();
+    lights.push(Light::new(
+        vec3 {
+            x: -10.0,
+            y: 20.0,
+            z: 0.0,
+        },
+        1.0,
+    ));
+    lights.push(Light::new(
+        vec3 {
+            x: 10.0,
+            y: 30.0,
+            z: -10.0,
+        },
+        0.5,
+    ));
+
+    return (spheres, lights);
+}
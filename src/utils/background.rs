@@ -0,0 +1,246 @@
+use crate::utils::sky::Sky;
+use crate::utils::tonemap::srgb_to_linear;
+use crate::utils::vec3::vec3;
+
+/// Kind of the plain (not image based) background
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundMode {
+    Gradient,
+    Solid,
+}
+
+/// Radiance arriving from directions in which a ray does not hit any object
+pub enum Background {
+    /// Same color in every direction
+    Solid(vec3),
+    /// Linear blend depending on the y-component of the direction, from `bottom` (straight
+    /// down) to `top` (straight up)
+    Gradient { top: vec3, bottom: vec3 },
+    /// Equirectangular environment map that also lights the scene
+    Environment(EnvironmentMap),
+    /// Analytic daylight sky
+    Sky(Sky),
+}
+
+/// Equirectangular (latitude-longitude) image of the radiance arriving from all directions. The
+/// center of the image lies in the direction of -z, the upper edge is +y.
+///
+/// For importance sampling the map is treated as a piecewise constant distribution proportional
+/// to the luminance of the pixels (weighted by the solid angle they cover): a row is chosen by
+/// the marginal distribution of the rows, then a column by the conditional distribution of that
+/// row.
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<vec3>,
+    /// Rotation around the y-axis in radians
+    rotation: f32,
+    /// Cumulative distribution of the rows, `height + 1` values from 0 to 1
+    marginal_cdf: Vec<f32>,
+    /// Cumulative distribution of the columns of each row, `width + 1` values per row
+    conditional_cdf: Vec<f32>,
+}
+
+/// Image shown behind the scene in screen space: camera rays that do not hit any object show
+/// the pixel of the image at the same position (the image is scaled to the size of the
+/// rendered image). Reflections and refractions still see the `Background`.
+pub struct Backplate {
+    width: u32,
+    height: u32,
+    pixels: Vec<vec3>,
+}
+
+impl Backplate {
+    /// Loads an image. With `linearize` the sRGB encoded colors are converted to linear values,
+    /// so that they are reproduced after the sRGB encoding of the output.
+    pub fn load(path: &std::path::Path, linearize: bool) -> Result<Backplate, image::ImageError> {
+        let img = image::open(path)?.into_rgb32f();
+        let convert = |c: f32| if linearize { srgb_to_linear(c) } else { c };
+        let pixels = img
+            .pixels()
+            .map(|p| vec3 {
+                x: convert(p.0[0]),
+                y: convert(p.0[1]),
+                z: convert(p.0[2]),
+            })
+            .collect();
+        return Ok(Backplate {
+            width: img.width(),
+            height: img.height(),
+            pixels: pixels,
+        });
+    }
+
+    /// Color at the position (`x`, `y`) relative to the upper left corner of the image, both
+    /// in [0, 1)
+    pub fn color(&self, x: f32, y: f32) -> vec3 {
+        let column = ((x * self.width as f32) as u32).min(self.width - 1);
+        let row = ((y * self.height as f32) as u32).min(self.height - 1);
+        return self.pixels[(row * self.width + column) as usize];
+    }
+}
+
+/// Normalizes the running sums in `cdf` (whose first value is 0) to end at 1. Returns the
+/// total before normalization.
+fn normalize_cdf(cdf: &mut [f32]) -> f32 {
+    let intervals = (cdf.len() - 1) as f32;
+    let total = cdf[cdf.len() - 1];
+    for (i, value) in cdf.iter_mut().enumerate() {
+        *value = if total > 0.0 {
+            *value / total
+        } else {
+            i as f32 / intervals
+        };
+    }
+    return total;
+}
+
+/// Index `i` with `cdf[i] <= u < cdf[i + 1]` and the relative position of `u` inside that
+/// interval
+fn sample_cdf(cdf: &[f32], u: f32) -> (usize, f32) {
+    let i = cdf
+        .partition_point(|&value| value <= u)
+        .saturating_sub(1)
+        .min(cdf.len() - 2);
+    let width = cdf[i + 1] - cdf[i];
+    let offset = if width > 0.0 {
+        (u - cdf[i]) / width
+    } else {
+        0.5
+    };
+    return (i, offset.clamp(0.0, 1.0));
+}
+
+impl EnvironmentMap {
+    /// Loads an equirectangular image. HDR and EXR files hold linear radiance, other formats
+    /// are assumed to be sRGB encoded. The radiance is multiplied by `intensity`, `rotation`
+    /// rotates the map around the y-axis (in degrees).
+    pub fn load(
+        path: &std::path::Path,
+        rotation: f32,
+        intensity: f32,
+    ) -> Result<EnvironmentMap, image::ImageError> {
+        let is_hdr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+        let (width, height, pixels, linear) = if is_hdr {
+            // `image::open` converts Radiance files to 8 bit, so they are decoded directly
+            let decoder = image::codecs::hdr::HdrDecoder::new(std::io::BufReader::new(
+                std::fs::File::open(path)?,
+            ))?;
+            let metadata = decoder.metadata();
+            let pixels = decoder.read_image_hdr()?;
+            (metadata.width, metadata.height, pixels, true)
+        } else {
+            let img = image::open(path)?;
+            let linear = matches!(
+                img.color(),
+                image::ColorType::Rgb32F | image::ColorType::Rgba32F
+            );
+            let img = img.into_rgb32f();
+            (
+                img.width(),
+                img.height(),
+                img.pixels().copied().collect(),
+                linear,
+            )
+        };
+        let pixels = pixels
+            .into_iter()
+            .map(|p| {
+                let [r, g, b] = p.0;
+                let color = if linear {
+                    vec3 { x: r, y: g, z: b }
+                } else {
+                    vec3 {
+                        x: srgb_to_linear(r),
+                        y: srgb_to_linear(g),
+                        z: srgb_to_linear(b),
+                    }
+                };
+                color * intensity
+            })
+            .collect();
+        return Ok(EnvironmentMap::new(width, height, pixels, rotation));
+    }
+
+    pub fn new(width: u32, height: u32, pixels: Vec<vec3>, rotation: f32) -> EnvironmentMap {
+        let (w, h) = (width as usize, height as usize);
+        let mut marginal_cdf = vec![0.0; h + 1];
+        let mut conditional_cdf = vec![0.0; (w + 1) * h];
+        for row in 0..h {
+            let sin_theta = f32::sin(std::f32::consts::PI * (row as f32 + 0.5) / h as f32);
+            let cdf = &mut conditional_cdf[row * (w + 1)..(row + 1) * (w + 1)];
+            for column in 0..w {
+                cdf[column + 1] = cdf[column] + pixels[row * w + column].luminance() * sin_theta;
+            }
+            marginal_cdf[row + 1] = marginal_cdf[row] + normalize_cdf(cdf);
+        }
+        normalize_cdf(&mut marginal_cdf);
+        return EnvironmentMap {
+            width: width,
+            height: height,
+            pixels: pixels,
+            rotation: rotation.to_radians(),
+            marginal_cdf: marginal_cdf,
+            conditional_cdf: conditional_cdf,
+        };
+    }
+
+    /// Direction corresponding to the point (`u`, `v`) of the map, both in [0, 1]
+    fn direction(&self, u: f32, v: f32) -> vec3 {
+        let phi = (u - 0.5) * 2.0 * std::f32::consts::PI - self.rotation;
+        let theta = v * std::f32::consts::PI;
+        return vec3 {
+            x: f32::sin(theta) * f32::sin(phi),
+            y: f32::cos(theta),
+            z: -f32::sin(theta) * f32::cos(phi),
+        };
+    }
+
+    /// Pixel (column, row) seen in the (normalized) `direction`
+    fn pixel(&self, direction: vec3) -> (u32, u32) {
+        let phi = f32::atan2(direction.x, -direction.z) + self.rotation;
+        let u = (0.5 + phi / (2.0 * std::f32::consts::PI)).rem_euclid(1.0);
+        let v = f32::acos(direction.y.clamp(-1.0, 1.0)) / std::f32::consts::PI;
+        let column = ((u * self.width as f32) as u32).min(self.width - 1);
+        let row = ((v * self.height as f32) as u32).min(self.height - 1);
+        return (column, row);
+    }
+
+    /// Radiance arriving from the (normalized) `direction`
+    pub fn radiance(&self, direction: vec3) -> vec3 {
+        let (column, row) = self.pixel(direction);
+        return self.pixels[(row * self.width + column) as usize];
+    }
+
+    /// Chooses a direction proportional to the radiance of the map for two uniformly distributed
+    /// numbers `u1`, `u2` in [0, 1). Returns the direction, the radiance from there and the
+    /// probability density of the direction (with respect to the solid angle).
+    pub fn sample(&self, u1: f32, u2: f32) -> (vec3, vec3, f32) {
+        let w = self.width as usize;
+        let (row, row_offset) = sample_cdf(&self.marginal_cdf, u1);
+        let (column, column_offset) = sample_cdf(
+            &self.conditional_cdf[row * (w + 1)..(row + 1) * (w + 1)],
+            u2,
+        );
+
+        let u = (column as f32 + column_offset) / self.width as f32;
+        let v = (row as f32 + row_offset) / self.height as f32;
+        let direction = self.direction(u, v);
+
+        let row_probability = self.marginal_cdf[row + 1] - self.marginal_cdf[row];
+        let cdf = &self.conditional_cdf[row * (w + 1)..(row + 1) * (w + 1)];
+        let column_probability = cdf[column + 1] - cdf[column];
+        // density over the image, converted to the solid angle covered by a pixel
+        let image_pdf = row_probability * column_probability * (self.width * self.height) as f32;
+        let sin_theta = f32::sin(std::f32::consts::PI * v);
+        let pdf = if sin_theta > 0.0 {
+            image_pdf / (2.0 * std::f32::consts::PI * std::f32::consts::PI * sin_theta)
+        } else {
+            0.0
+        };
+        return (direction, self.pixels[row * w + column], pdf);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::utils::shape::{Hit, Shape};
+use crate::utils::vec3::vec3;
+
+/// Max. number of primitives stored in a leaf of the hierarchy
+const MAX_PRIMITIVES_PER_LEAF: usize = 4;
+
+/// Axis-aligned bounding box
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: vec3,
+    pub max: vec3,
+}
+
+impl Aabb {
+    pub const fn new(min: vec3, max: vec3) -> Aabb {
+        Aabb { min: min, max: max }
+    }
+
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: vec3 {
+                x: f32::INFINITY,
+                y: f32::INFINITY,
+                z: f32::INFINITY,
+            },
+            max: vec3 {
+                x: f32::NEG_INFINITY,
+                y: f32::NEG_INFINITY,
+                z: f32::NEG_INFINITY,
+            },
+        }
+    }
+
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb {
+            min: vec3 {
+                x: f32::min(self.min.x, other.min.x),
+                y: f32::min(self.min.y, other.min.y),
+                z: f32::min(self.min.z, other.min.z),
+            },
+            max: vec3 {
+                x: f32::max(self.max.x, other.max.x),
+                y: f32::max(self.max.y, other.max.y),
+                z: f32::max(self.max.z, other.max.z),
+            },
+        }
+    }
+
+    pub fn centroid(&self) -> vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    fn axis(v: vec3, axis: usize) -> f32 {
+        match axis {
+            0 => v.x,
+            1 => v.y,
+            _ => v.z,
+        }
+    }
+
+    /// Slab test. Returns `true` if the ray enters the box before `t_max`.
+    pub fn hit(&self, origin: vec3, inv_direction: vec3, t_max: f32) -> bool {
+        let mut t_enter: f32 = 0.0;
+        let mut t_exit = t_max;
+        for axis in 0..3 {
+            let o = Aabb::axis(origin, axis);
+            let inv_d = Aabb::axis(inv_direction, axis);
+            let t0 = (Aabb::axis(self.min, axis) - o) * inv_d;
+            let t1 = (Aabb::axis(self.max, axis) - o) * inv_d;
+            t_enter = f32::max(t_enter, f32::min(t0, t1));
+            t_exit = f32::min(t_exit, f32::max(t0, t1));
+        }
+        return t_enter <= t_exit;
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Node {
+    Leaf {
+        bounds: Aabb,
+        start: usize,
+        count: usize,
+    },
+    Interior {
+        bounds: Aabb,
+        left: usize,
+        right: usize,
+    },
+}
+
+/// Bounding volume hierarchy over a list of shapes.
+///
+/// The hierarchy refers to the shapes by their index, so it has to be queried with the same
+/// list of shapes it was built from.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build(shapes: &[Box<dyn Shape>]) -> Bvh {
+        let bounds: Vec<Aabb> = shapes.iter().map(|s| s.bounding_box()).collect();
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            indices: (0..bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build_node(&bounds, 0, bounds.len());
+        }
+        return bvh;
+    }
+
+    /// Recursively builds the node over `indices[start..end]` and returns its index. The
+    /// primitives are split at the median of their centroids along the longest axis.
+    fn build_node(&mut self, bounds: &[Aabb], start: usize, end: usize) -> usize {
+        let node_bounds = self.indices[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, i| acc.union(bounds[*i]));
+        let node_index = self.nodes.len();
+
+        if end - start <= MAX_PRIMITIVES_PER_LEAF {
+            self.nodes.push(Node::Leaf {
+                bounds: node_bounds,
+                start: start,
+                count: end - start,
+            });
+            return node_index;
+        }
+
+        let centroid_bounds = self.indices[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, i| {
+                let c = bounds[*i].centroid();
+                acc.union(Aabb::new(c, c))
+            });
+        let extent = centroid_bounds.max - centroid_bounds.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = start + (end - start) / 2;
+        self.indices[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            let ca = Aabb::axis(bounds[*a].centroid(), axis);
+            let cb = Aabb::axis(bounds[*b].centroid(), axis);
+            ca.total_cmp(&cb)
+        });
+
+        // placeholder, replaced once both children are built
+        self.nodes.push(Node::Leaf {
+            bounds: node_bounds,
+            start: start,
+            count: 0,
+        });
+        let left = self.build_node(bounds, start, mid);
+        let right = self.build_node(bounds, mid, end);
+        self.nodes[node_index] = Node::Interior {
+            bounds: node_bounds,
+            left: left,
+            right: right,
+        };
+        return node_index;
+    }
+
+    /// Finds the nearest intersection of the ray with a distance between `min_dist` and
+    /// `max_dist`.
+    pub fn nearest_hit<'a>(
+        &self,
+        shapes: &'a [Box<dyn Shape>],
+        origin: vec3,
+        direction: vec3,
+        min_dist: f32,
+        max_dist: f32,
+    ) -> Option<Hit<'a>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let inv_direction = vec3 {
+            x: 1.0 / direction.x,
+            y: 1.0 / direction.y,
+            z: 1.0 / direction.z,
+        };
+
+        let mut nearest: Option<Hit> = None;
+        let mut nearest_dist = max_dist;
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                Node::Leaf {
+                    bounds,
+                    start,
+                    count,
+                } => {
+                    if !bounds.hit(origin, inv_direction, nearest_dist) {
+                        continue;
+                    }
+                    for i in self.indices[start..start + count].iter() {
+                        if let Some(hit) = shapes[*i].intersect(origin, direction, min_dist) {
+                            if hit.distance <= nearest_dist {
+                                nearest_dist = hit.distance;
+                                nearest = Some(hit);
+                            }
+                        }
+                    }
+                }
+                Node::Interior {
+                    bounds,
+                    left,
+                    right,
+                } => {
+                    if bounds.hit(origin, inv_direction, nearest_dist) {
+                        stack.push(right);
+                        stack.push(left);
+                    }
+                }
+            }
+        }
+        return nearest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::material::Material;
+    use crate::utils::sphere::Sphere;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_point(rng: &mut SmallRng, extent: f32) -> vec3 {
+        return vec3 {
+            x: rng.gen_range(-extent..extent),
+            y: rng.gen_range(-extent..extent),
+            z: rng.gen_range(-extent..extent),
+        };
+    }
+
+    #[test]
+    fn nearest_hit_matches_linear_scan() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        let shapes: Vec<Box<dyn Shape>> = (0..200)
+            .map(|_| {
+                let center = random_point(&mut rng, 10.0);
+                let radius = rng.gen_range(0.1..1.5);
+                Box::new(Sphere::new(center, radius, Material::default())) as Box<dyn Shape>
+            })
+            .collect();
+        let bvh = Bvh::build(&shapes);
+
+        let (min_dist, max_dist) = (1e-3, 1000.0);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let origin = random_point(&mut rng, 15.0);
+            let direction = random_point(&mut rng, 1.0).normalize();
+
+            let linear = shapes
+                .iter()
+                .filter_map(|shape| shape.intersect(origin, direction, min_dist))
+                .map(|hit| hit.distance)
+                .filter(|distance| *distance <= max_dist)
+                .fold(None, |nearest: Option<f32>, distance| {
+                    Some(nearest.map_or(distance, |n| n.min(distance)))
+                });
+            let accelerated = bvh
+                .nearest_hit(&shapes, origin, direction, min_dist, max_dist)
+                .map(|hit| hit.distance);
+
+            assert_eq!(
+                linear, accelerated,
+                "ray from {:?} along {:?}",
+                origin, direction
+            );
+            hits += linear.is_some() as usize;
+        }
+        // Make sure the comparison is not dominated by misses
+        assert!(hits > 200);
+    }
+}
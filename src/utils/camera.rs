@@ -0,0 +1,222 @@
+use crate::utils::sampling;
+use crate::utils::vec3::vec3;
+
+/// Mapping of the points of the image to rays
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Projection {
+    /// Pinhole (or thin lens) camera with the vertical field of view `vfov`
+    Perspective,
+    /// Parallel rays along the viewing direction, starting on a rectangle of width `view_width`
+    /// around the eye
+    Orthographic,
+    /// 360° panorama: the horizontal axis of the image covers all longitudes, the vertical axis
+    /// all latitudes
+    Equirectangular,
+    /// Equidistant fisheye covering `fisheye_fov` degrees across the shorter side of the image
+    Fisheye,
+}
+
+/// Camera at `eye` looking at `target`. By default it is a perspective pinhole camera; with an
+/// aperture (`with_lens`) it becomes a thin lens camera producing depth of field, other
+/// projections can be selected with `with_projection`.
+///
+/// The orthonormal basis of the camera is calculated once when the camera is created, so that
+/// generating a ray only requires a linear combination of the basis vectors.
+#[derive(Debug, Copy, Clone)]
+pub struct Camera {
+    pub eye: vec3,
+    pub target: vec3,
+    /// Approximate up direction of the image (does not have to be perpendicular to the viewing
+    /// direction)
+    pub up: vec3,
+    /// Vertical field of view in degrees
+    pub vfov: f32,
+    /// Width of the image divided by its height
+    pub aspect: f32,
+    /// Rotation of the camera around the viewing direction in degrees (counterclockwise)
+    pub roll: f32,
+    /// Radius of the lens, 0 for a pinhole camera
+    pub aperture: f32,
+    /// Distance of the plane in focus, `None` for the distance to the target
+    pub focus_distance: Option<f32>,
+    pub projection: Projection,
+    /// Width of the visible area of an orthographic camera
+    pub view_width: f32,
+    /// Field of view of a fisheye camera in degrees
+    pub fisheye_fov: f32,
+    forward: vec3,
+    right: vec3,
+    true_up: vec3,
+    tan_half_vfov: f32,
+    focus: f32,
+    /// Horizontal shift of the image plane (relative to its distance) for off-axis stereo
+    shift: f32,
+}
+
+impl Camera {
+    pub fn new(eye: vec3, target: vec3, up: vec3, vfov: f32, aspect: f32, roll: f32) -> Camera {
+        let forward = (target - eye).normalize();
+        let mut up_hint = up;
+        up_hint = up_hint.normalize();
+        if forward.cross(up_hint).norm() < 1e-4 {
+            // looking (almost) parallel to the up vector, any perpendicular vector will do
+            up_hint = if forward.y.abs() > 0.9 {
+                vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: f32::signum(forward.y),
+                }
+            } else {
+                vec3 {
+                    x: 0.0,
+                    y: 1.0,
+                    z: 0.0,
+                }
+            };
+        }
+        let right = forward.cross(up_hint).normalize();
+        let true_up = right.cross(forward);
+
+        let (sin_roll, cos_roll) = f32::sin_cos(roll.to_radians());
+        return Camera {
+            eye: eye,
+            target: target,
+            up: up,
+            vfov: vfov,
+            aspect: aspect,
+            roll: roll,
+            aperture: 0.0,
+            focus_distance: None,
+            projection: Projection::Perspective,
+            view_width: 20.0,
+            fisheye_fov: 180.0,
+            forward: forward,
+            right: right * cos_roll + true_up * sin_roll,
+            true_up: true_up * cos_roll - right * sin_roll,
+            tan_half_vfov: f32::tan(vfov.to_radians() / 2.0),
+            focus: (target - eye).norm(),
+            shift: 0.0,
+        };
+    }
+
+    pub fn with_lens(mut self, aperture: f32, focus_distance: Option<f32>) -> Camera {
+        self.aperture = aperture;
+        self.focus_distance = focus_distance;
+        self.focus = focus_distance.unwrap_or((self.target - self.eye).norm());
+        self
+    }
+
+    pub fn with_projection(
+        mut self,
+        projection: Projection,
+        view_width: f32,
+        fisheye_fov: f32,
+    ) -> Camera {
+        self.projection = projection;
+        self.view_width = view_width;
+        self.fisheye_fov = fisheye_fov;
+        self
+    }
+
+    /// Same camera moved to `eye`, still looking at the target
+    pub fn with_eye(&self, eye: vec3) -> Camera {
+        let mut camera = Camera::new(eye, self.target, self.up, self.vfov, self.aspect, self.roll)
+            .with_lens(self.aperture, self.focus_distance)
+            .with_projection(self.projection, self.view_width, self.fisheye_fov);
+        camera.shift = self.shift;
+        return camera;
+    }
+
+    /// Cameras of the left and the right eye, moved by half of `eye_separation` to the sides.
+    /// Both keep the viewing direction and shift their image planes instead (off-axis
+    /// projection), so that objects at the distance `convergence` appear at the same position
+    /// in both images (zero parallax) without the vertical parallax of toed-in cameras.
+    pub fn stereo_pair(&self, eye_separation: f32, convergence: f32) -> (Camera, Camera) {
+        let offset = self.right * (eye_separation / 2.0);
+        let shift = eye_separation / (2.0 * convergence);
+
+        let mut left = *self;
+        left.eye = self.eye - offset;
+        left.target = self.target - offset;
+        left.shift = self.shift + shift;
+
+        let mut right = *self;
+        right.eye = self.eye + offset;
+        right.target = self.target + offset;
+        right.shift = self.shift - shift;
+        return (left, right);
+    }
+
+    /// `true` if the rays have to be sampled over the lens (only perspective cameras with an
+    /// aperture)
+    pub fn has_lens(&self) -> bool {
+        return self.projection == Projection::Perspective && self.aperture > 0.0;
+    }
+
+    /// Distance of the plane in focus
+    pub fn focus(&self) -> f32 {
+        return self.focus;
+    }
+
+    /// Origin and direction of the ray through the point (`u`, `v`) of the image, where
+    /// (-1, -1) is the lower left and (1, 1) the upper right corner. Returns `None` for points
+    /// outside of the image circle of a fisheye camera.
+    pub fn ray(&self, u: f32, v: f32) -> Option<(vec3, vec3)> {
+        return match self.projection {
+            Projection::Perspective => Some((self.eye, self.direction(u, v))),
+            Projection::Orthographic => {
+                let half_width = self.view_width / 2.0;
+                let origin = self.eye
+                    + self.right * (u * half_width)
+                    + self.true_up * (v * half_width / self.aspect);
+                Some((origin, self.forward))
+            }
+            Projection::Equirectangular => {
+                let longitude = u * std::f32::consts::PI;
+                let latitude = v * std::f32::consts::FRAC_PI_2;
+                let direction = self.forward * (f32::cos(latitude) * f32::cos(longitude))
+                    + self.right * (f32::cos(latitude) * f32::sin(longitude))
+                    + self.true_up * f32::sin(latitude);
+                Some((self.eye, direction))
+            }
+            Projection::Fisheye => {
+                // the image circle touches the shorter sides of the image
+                let (x, y) = if self.aspect >= 1.0 {
+                    (u * self.aspect, v)
+                } else {
+                    (u, v / self.aspect)
+                };
+                let r = f32::sqrt(x * x + y * y);
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * self.fisheye_fov.to_radians() / 2.0;
+                let phi = f32::atan2(y, x);
+                let direction = self.forward * f32::cos(theta)
+                    + (self.right * f32::cos(phi) + self.true_up * f32::sin(phi)) * f32::sin(theta);
+                Some((self.eye, direction))
+            }
+        };
+    }
+
+    /// Direction of the ray of a perspective camera through the point (`u`, `v`) of the image
+    pub fn direction(&self, u: f32, v: f32) -> vec3 {
+        return (self.forward
+            + self.right * (u * self.aspect * self.tan_half_vfov + self.shift)
+            + self.true_up * (v * self.tan_half_vfov))
+            .normalize();
+    }
+
+    /// Origin and direction of a ray through the point (`u`, `v`) of the image, starting at the
+    /// point of the lens given by two uniformly distributed numbers `lens_u`, `lens_v` in [0, 1).
+    /// All rays through the same point of the image meet in the plane in focus.
+    pub fn lens_ray(&self, u: f32, v: f32, lens_u: f32, lens_v: f32) -> (vec3, vec3) {
+        let direction = self.direction(u, v);
+        let focus_point = self.eye + direction * (self.focus / (direction * self.forward));
+        let (x, y) = sampling::concentric_disk(lens_u, lens_v);
+        let origin =
+            self.eye + self.right * (x * self.aperture) + self.true_up * (y * self.aperture);
+        return (origin, (focus_point - origin).normalize());
+    }
+}
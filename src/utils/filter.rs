@@ -0,0 +1,158 @@
+/// Shape of the reconstruction filter
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterKind {
+    /// Constant weight inside the radius
+    Box,
+    /// Weight falling off linearly towards the radius
+    Tent,
+    /// Gaussian with a standard deviation of a third of the radius, shifted to be zero at the
+    /// radius
+    Gaussian,
+    /// Mitchell-Netravali cubic with B = C = 1/3, scaled to the radius
+    Mitchell,
+    /// Sinc windowed by a sinc that is stretched to the radius
+    Lanczos,
+}
+
+impl FilterKind {
+    /// Radius used if none is given explicitly
+    pub fn default_radius(&self) -> f32 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        }
+    }
+}
+
+/// Separable filter weighting the contribution of a sample to the pixels around it
+#[derive(Debug, Copy, Clone)]
+pub struct Filter {
+    pub kind: FilterKind,
+    /// Radius of the filter in pixels
+    pub radius: f32,
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    let pi_x = std::f32::consts::PI * x;
+    return f32::sin(pi_x) / pi_x;
+}
+
+impl Filter {
+    pub const fn new(kind: FilterKind, radius: f32) -> Filter {
+        Filter {
+            kind: kind,
+            radius: radius,
+        }
+    }
+
+    /// `true` for the box filter that covers exactly one pixel, i.e. every sample only
+    /// contributes to the pixel it belongs to
+    pub fn is_pixel_box(&self) -> bool {
+        return self.kind == FilterKind::Box && self.radius == 0.5;
+    }
+
+    /// Weight in one dimension at distance `x` (in pixels) from the center of the filter
+    fn weight_1d(&self, x: f32) -> f32 {
+        let x = x.abs();
+        if x > self.radius {
+            return 0.0;
+        }
+        return match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => 1.0 - x / self.radius,
+            FilterKind::Gaussian => {
+                let sigma = self.radius / 3.0;
+                let gaussian = |d: f32| f32::exp(-d * d / (2.0 * sigma * sigma));
+                f32::max(0.0, gaussian(x) - gaussian(self.radius))
+            }
+            FilterKind::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                let x = 2.0 * x / self.radius;
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                } else {
+                    ((-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                }
+            }
+            FilterKind::Lanczos => sinc(x) * sinc(x / self.radius),
+        };
+    }
+
+    /// Weight of a sample at the offset (`dx`, `dy`) from the center of a pixel
+    pub fn weight(&self, dx: f32, dy: f32) -> f32 {
+        return self.weight_1d(dx) * self.weight_1d(dy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [FilterKind; 5] = [
+        FilterKind::Box,
+        FilterKind::Tent,
+        FilterKind::Gaussian,
+        FilterKind::Mitchell,
+        FilterKind::Lanczos,
+    ];
+
+    #[test]
+    fn weights_are_symmetric() {
+        for kind in KINDS {
+            let filter = Filter::new(kind, kind.default_radius());
+            for i in 0..=20 {
+                for j in 0..=20 {
+                    let dx = i as f32 * filter.radius / 10.0;
+                    let dy = j as f32 * filter.radius / 10.0;
+                    let weight = filter.weight(dx, dy);
+                    assert_eq!(weight, filter.weight(-dx, dy), "{:?}", kind);
+                    assert_eq!(weight, filter.weight(dx, -dy), "{:?}", kind);
+                    assert_eq!(weight, filter.weight(-dx, -dy), "{:?}", kind);
+                    assert_eq!(weight, filter.weight(dy, dx), "{:?}", kind);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pixel_box_gives_plain_average() {
+        let filter = Filter::new(FilterKind::Box, 0.5);
+        assert!(filter.is_pixel_box());
+
+        // offsets of the samples from the pixel center and their values
+        let samples = [
+            ((-0.45, -0.3), 0.2),
+            ((0.1, 0.49), 0.9),
+            ((0.0, 0.0), 0.4),
+            ((0.3, -0.2), 0.7),
+            ((-0.25, 0.35), 0.1),
+        ];
+        let mut weighted_sum = 0.0;
+        let mut weight_sum = 0.0;
+        for ((dx, dy), value) in samples {
+            let weight = filter.weight(dx, dy);
+            weighted_sum += weight * value;
+            weight_sum += weight;
+        }
+        let average = samples.iter().map(|(_, value)| value).sum::<f32>() / samples.len() as f32;
+        assert!((weighted_sum / weight_sum - average).abs() < 1e-6);
+
+        // samples of the neighbouring pixels do not contribute
+        assert_eq!(filter.weight(0.6, 0.0), 0.0);
+        assert_eq!(filter.weight(0.0, -0.6), 0.0);
+    }
+}
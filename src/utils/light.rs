@@ -1,10 +1,81 @@
+use crate::utils::material::Material;
+use crate::utils::plane::Plane;
+use crate::utils::sampling;
 use crate::utils::vec3::vec3;
 
+/// Geometry of a light. Area lights cast soft shadows; they are sampled at several points per
+/// shading point and are not visible themselves.
+#[derive(Debug, Copy, Clone)]
+pub enum LightShape {
+    Point,
+    Sphere {
+        radius: f32,
+    },
+    /// Rectangle around the position of the light with the extent `size` along `tangent` and
+    /// `bitangent`
+    Rectangle {
+        normal: vec3,
+        tangent: vec3,
+        bitangent: vec3,
+        size: (f32, f32),
+    },
+    Disk {
+        normal: vec3,
+        radius: f32,
+    },
+    /// Infinitely far away light (e.g. the sun) shining in `direction`, the position is ignored
+    Directional {
+        direction: vec3,
+    },
+    /// Point light shining into a cone around `direction`. `cos_angle` is the cosine of the
+    /// angle between the axis and the edge of the cone, the intensity fades out towards the
+    /// edge starting at the angle with the cosine `cos_falloff`.
+    Spot {
+        direction: vec3,
+        cos_angle: f32,
+        cos_falloff: f32,
+    },
+}
+
+/// Decrease of the intensity of a light with the distance `d` to the lit point
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Attenuation {
+    /// Constant intensity, independent of the distance
+    None,
+    /// Physically correct falloff: `1 / d^2`
+    InverseSquare,
+    /// `1 / (constant + linear * d + quadratic * d^2)`
+    Polynomial {
+        constant: f32,
+        linear: f32,
+        quadratic: f32,
+    },
+}
+
+impl Attenuation {
+    /// Factor the intensity is scaled with at the distance `distance`
+    pub fn factor(&self, distance: f32) -> f32 {
+        return match *self {
+            Attenuation::None => 1.0,
+            Attenuation::InverseSquare => 1.0 / (distance * distance),
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => 1.0 / (constant + linear * distance + quadratic * distance * distance),
+        };
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct Light {
     pub pos: vec3,
     pub intensity: f32,
+    /// RGB color the intensity is multiplied with
+    pub color: vec3,
+    pub shape: LightShape,
+    /// Falloff with the distance, ignored by directional lights
+    pub attenuation: Attenuation,
 }
 
 impl Light {
@@ -12,6 +83,138 @@ impl Light {
         Light {
             pos: pos,
             intensity: intensity,
+            color: vec3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            shape: LightShape::Point,
+            attenuation: Attenuation::None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_color(mut self, color: vec3) -> Light {
+        self.color = color;
+        self
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Light {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn with_sphere(mut self, radius: f32) -> Light {
+        self.shape = LightShape::Sphere { radius: radius };
+        self
+    }
+
+    /// Rectangle facing in the direction of `normal`, `size` is the extent (width, depth) as
+    /// for `Plane::from_normal`
+    pub fn with_rectangle(mut self, normal: vec3, size: (f32, f32)) -> Light {
+        let plane = Plane::from_normal(self.pos, normal, size, Material::default());
+        self.shape = LightShape::Rectangle {
+            normal: plane.normal,
+            tangent: plane.tangent,
+            bitangent: plane.bitangent,
+            size: plane.size,
+        };
+        self
+    }
+
+    pub fn with_disk(mut self, mut normal: vec3, radius: f32) -> Light {
+        self.shape = LightShape::Disk {
+            normal: normal.normalize(),
+            radius: radius,
+        };
+        self
+    }
+
+    pub fn with_directional(mut self, mut direction: vec3) -> Light {
+        self.shape = LightShape::Directional {
+            direction: direction.normalize(),
+        };
+        self
+    }
+
+    /// Spot light shining in `direction`. `angle` is the angle between the axis and the edge of
+    /// the cone in degrees, `falloff` the width of the soft edge inside the cone in degrees.
+    pub fn with_spot(mut self, mut direction: vec3, angle: f32, falloff: f32) -> Light {
+        self.shape = LightShape::Spot {
+            direction: direction.normalize(),
+            cos_angle: f32::cos(angle.to_radians()),
+            cos_falloff: f32::cos(f32::max(angle - falloff, 0.0).to_radians()),
+        };
+        self
+    }
+
+    /// `true` for lights that are sampled with a single shadow ray
+    pub fn is_point(&self) -> bool {
+        return matches!(
+            self.shape,
+            LightShape::Point | LightShape::Directional { .. } | LightShape::Spot { .. }
+        );
+    }
+
+    /// Light arriving at `from` from the point on the light given by two uniformly distributed
+    /// numbers `u1`, `u2` in [0, 1). Returns the (normalized) direction to that point, its
+    /// distance (infinite for directional lights) and the factor by which the intensity emitted
+    /// towards `from` is scaled (including the attenuation). Rectangles and disks emit to both
+    /// sides with the intensity falling off with the cosine of the angle to their normal; for
+    /// spheres a point on the disk facing `from` is chosen.
+    pub fn sample(&self, from: vec3, u1: f32, u2: f32) -> (vec3, f32, f32) {
+        let (point, factor) = match self.shape {
+            LightShape::Point => (self.pos, 1.0),
+            LightShape::Sphere { radius } => {
+                let (tangent, bitangent) =
+                    sampling::orthonormal_basis((from - self.pos).normalize());
+                let (x, y) = sampling::concentric_disk(u1, u2);
+                (
+                    self.pos + tangent * (x * radius) + bitangent * (y * radius),
+                    1.0,
+                )
+            }
+            LightShape::Rectangle {
+                normal,
+                tangent,
+                bitangent,
+                size,
+            } => {
+                let point =
+                    self.pos + tangent * ((u1 - 0.5) * size.0) + bitangent * ((u2 - 0.5) * size.1);
+                (point, f32::abs((from - point).normalize() * normal))
+            }
+            LightShape::Disk { normal, radius } => {
+                let (tangent, bitangent) = sampling::orthonormal_basis(normal);
+                let (x, y) = sampling::concentric_disk(u1, u2);
+                let point = self.pos + tangent * (x * radius) + bitangent * (y * radius);
+                (point, f32::abs((from - point).normalize() * normal))
+            }
+            LightShape::Directional { direction } => {
+                return (-direction, f32::INFINITY, 1.0);
+            }
+            LightShape::Spot {
+                direction,
+                cos_angle,
+                cos_falloff,
+            } => {
+                let cos_theta = (from - self.pos).normalize() * direction;
+                let factor = if cos_theta >= cos_falloff {
+                    1.0
+                } else if cos_theta <= cos_angle {
+                    0.0
+                } else {
+                    // smoothstep between the edge of the cone and the start of the falloff
+                    let t = (cos_theta - cos_angle) / (cos_falloff - cos_angle);
+                    t * t * (3.0 - 2.0 * t)
+                };
+                (self.pos, factor)
+            }
+        };
+        let distance = (point - from).norm();
+        return (
+            (point - from).normalize(),
+            distance,
+            factor * self.attenuation.factor(distance),
+        );
+    }
+}
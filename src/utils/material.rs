@@ -1,6 +1,8 @@
+use crate::utils::texture::Texture;
 use crate::utils::vec3::vec3;
+use std::sync::Arc;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct Material {
     pub refractive_index: f32,
     pub diffuse_multiplier: f32,
@@ -9,6 +11,21 @@ pub struct Material {
     pub refraction_multiplier: f32,
     pub color: vec3,
     pub specular_exponent: f32,
+    /// Light emitted by the surface itself
+    pub emission: vec3,
+    /// Split the sum of the reflection and refraction multipliers according to the Fresnel
+    /// equations instead of using them as constant weights
+    pub fresnel: bool,
+    /// Texture replacing `color`
+    pub color_texture: Option<Arc<Texture>>,
+    /// Texture whose luminance scales `diffuse_multiplier`
+    pub diffuse_texture: Option<Arc<Texture>>,
+    /// Texture whose luminance scales `specular_multiplier`
+    pub specular_texture: Option<Arc<Texture>>,
+    /// Texture whose luminance scales `reflection_multiplier`
+    pub reflection_texture: Option<Arc<Texture>>,
+    /// Texture whose luminance scales `refraction_multiplier`
+    pub refraction_texture: Option<Arc<Texture>>,
 }
 
 impl Material {
@@ -29,7 +46,78 @@ impl Material {
             refraction_multiplier: refraction_multiplier,
             color: color,
             specular_exponent: specular_exponent,
+            emission: vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            fresnel: false,
+            color_texture: None,
+            diffuse_texture: None,
+            specular_texture: None,
+            reflection_texture: None,
+            refraction_texture: None,
+        }
+    }
+
+    pub const fn with_fresnel(mut self) -> Material {
+        self.fresnel = true;
+        self
+    }
+
+    pub const fn with_emission(mut self, emission: vec3) -> Material {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_color_texture(mut self, texture: Arc<Texture>) -> Material {
+        self.color_texture = Some(texture);
+        self
+    }
+
+    pub fn with_diffuse_texture(mut self, texture: Arc<Texture>) -> Material {
+        self.diffuse_texture = Some(texture);
+        self
+    }
+
+    pub fn with_specular_texture(mut self, texture: Arc<Texture>) -> Material {
+        self.specular_texture = Some(texture);
+        self
+    }
+
+    pub fn with_reflection_texture(mut self, texture: Arc<Texture>) -> Material {
+        self.reflection_texture = Some(texture);
+        self
+    }
+
+    pub fn with_refraction_texture(mut self, texture: Arc<Texture>) -> Material {
+        self.refraction_texture = Some(texture);
+        self
+    }
+
+    /// The material at a hit point (see `Texture::color`) with the textures applied
+    pub fn textured(mut self, uv: (f32, f32), point: vec3, object_point: vec3) -> Material {
+        let value = |texture: &Option<Arc<Texture>>| {
+            texture
+                .as_ref()
+                .map(|texture| texture.color(uv, point, object_point))
+        };
+        if let Some(color) = value(&self.color_texture) {
+            self.color = color;
+        }
+        if let Some(value) = value(&self.diffuse_texture) {
+            self.diffuse_multiplier *= value.luminance();
+        }
+        if let Some(value) = value(&self.specular_texture) {
+            self.specular_multiplier *= value.luminance();
         }
+        if let Some(value) = value(&self.reflection_texture) {
+            self.reflection_multiplier *= value.luminance();
+        }
+        if let Some(value) = value(&self.refraction_texture) {
+            self.refraction_multiplier *= value.luminance();
+        }
+        return self;
     }
 
     pub fn default() -> Material {
@@ -45,6 +133,17 @@ impl Material {
                 z: 0.0,
             },
             specular_exponent: 0.0,
+            emission: vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            fresnel: false,
+            color_texture: None,
+            diffuse_texture: None,
+            specular_texture: None,
+            reflection_texture: None,
+            refraction_texture: None,
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,58 @@
+use crate::utils::material::Material;
+use crate::utils::triangle::Triangle;
+use crate::utils::vec3::vec3;
+
+use std::sync::Arc;
+
+/// A triangle mesh, e.g. loaded from a Wavefront OBJ file
+pub struct Mesh {
+    pub triangles: Vec<Triangle>,
+}
+
+impl Mesh {
+    /// Loads all objects of an OBJ file. Faces are triangulated, the vertices are scaled by
+    /// `scale` and then moved by `position`. Vertex normals are used for smooth shading if the
+    /// file provides them.
+    pub fn load_obj(
+        path: &std::path::Path,
+        material: Material,
+        position: vec3,
+        scale: f32,
+    ) -> Result<Mesh, tobj::LoadError> {
+        let (models, _) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)?;
+        let material = Arc::new(material);
+
+        let mut triangles: Vec<Triangle> = Vec::new();
+        for model in models {
+            let mesh = model.mesh;
+            let vertex = |i: u32| {
+                vec3 {
+                    x: mesh.positions[3 * i as usize],
+                    y: mesh.positions[3 * i as usize + 1],
+                    z: mesh.positions[3 * i as usize + 2],
+                } * scale
+                    + position
+            };
+            let normal = |i: u32| vec3 {
+                x: mesh.normals[3 * i as usize],
+                y: mesh.normals[3 * i as usize + 1],
+                z: mesh.normals[3 * i as usize + 2],
+            };
+            for face in mesh.indices.chunks_exact(3) {
+                let normals = if mesh.normals.is_empty() {
+                    None
+                } else {
+                    Some([normal(face[0]), normal(face[1]), normal(face[2])])
+                };
+                triangles.push(Triangle::new(
+                    [vertex(face[0]), vertex(face[1]), vertex(face[2])],
+                    normals,
+                    Arc::clone(&material),
+                ));
+            }
+        }
+        return Ok(Mesh {
+            triangles: triangles,
+        });
+    }
+}
@@ -0,0 +1,127 @@
+use crate::utils::bvh::Aabb;
+use crate::utils::material::Material;
+use crate::utils::shape::{Hit, Shape};
+use crate::utils::vec3::vec3;
+
+/// Checkerboard pattern: every other tile uses `color` instead of the color of the material
+#[derive(Debug, Copy, Clone)]
+pub struct Checker {
+    pub color: vec3,
+    pub tile_size: f32,
+}
+
+/// A bounded plane (rectangle) spanned by `tangent` and `bitangent` around `center`
+#[derive(Debug, Clone)]
+pub struct Plane {
+    pub center: vec3,
+    pub normal: vec3,
+    pub tangent: vec3,
+    pub bitangent: vec3,
+    pub size: (f32, f32),
+    pub material: Material,
+    pub checker: Option<Checker>,
+}
+
+impl Plane {
+    /// `size` is the full extent of the rectangle along `tangent` and `bitangent`. The normal
+    /// is `tangent` x `bitangent`.
+    pub fn new(
+        center: vec3,
+        mut tangent: vec3,
+        mut bitangent: vec3,
+        size: (f32, f32),
+        material: Material,
+    ) -> Plane {
+        let tangent = tangent.normalize();
+        let bitangent = bitangent.normalize();
+        Plane {
+            center: center,
+            normal: tangent.cross(bitangent).normalize(),
+            tangent: tangent,
+            bitangent: bitangent,
+            size: size,
+            material: material,
+            checker: None,
+        }
+    }
+
+    /// Rectangle facing in the direction of `normal`. `size` is the extent (width, depth) of
+    /// the rectangle. For a normal pointing up (+y) the width is along the x-axis and the depth
+    /// along the z-axis.
+    pub fn from_normal(
+        center: vec3,
+        mut normal: vec3,
+        size: (f32, f32),
+        material: Material,
+    ) -> Plane {
+        let normal = normal.normalize();
+        let reference = if normal.z.abs() < 0.9 {
+            vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            }
+        } else {
+            vec3 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+            }
+        };
+        let bitangent = normal.cross(reference);
+        let tangent = bitangent.cross(normal);
+        Plane::new(center, tangent, bitangent, (size.1, size.0), material)
+    }
+
+    pub fn with_checker(mut self, checker: Checker) -> Plane {
+        self.checker = Some(checker);
+        self
+    }
+}
+
+impl Shape for Plane {
+    fn intersect(&self, origin: vec3, direction: vec3, min_dist: f32) -> Option<Hit<'_>> {
+        let denom = direction * self.normal;
+        if denom.abs() <= 0.0 {
+            return None;
+        }
+        let distance = ((self.center - origin) * self.normal) / denom;
+        if distance <= min_dist {
+            return None;
+        }
+        let local = origin + direction * distance - self.center;
+        let u = local * self.tangent;
+        let v = local * self.bitangent;
+        if u.abs() >= self.size.0 / 2.0 || v.abs() >= self.size.1 / 2.0 {
+            return None;
+        }
+        let mut color = None;
+        if let Some(checker) = self.checker {
+            let tile = f32::floor(u / checker.tile_size) + f32::floor(v / checker.tile_size);
+            if tile.rem_euclid(2.0) != 0.0 {
+                color = Some(checker.color);
+            }
+        }
+        return Some(Hit {
+            distance: distance,
+            normal: self.normal,
+            material: &self.material,
+            color: color,
+            // u along the bitangent and v along the tangent, so that the texture is not mirrored
+            // when seen from the side the normal points to
+            uv: (v / self.size.1 + 0.5, u / self.size.0 + 0.5),
+            object_point: local,
+        });
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let u = self.tangent * (self.size.0 / 2.0);
+        let v = self.bitangent * (self.size.1 / 2.0);
+        let mut bounds = Aabb::empty();
+        for corner in [u + v, u - v, -u + v, -u - v] {
+            let p = self.center + corner;
+            bounds = bounds.union(Aabb::new(p, p));
+        }
+        return bounds;
+    }
+}
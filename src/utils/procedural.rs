@@ -0,0 +1,201 @@
+use crate::utils::vec3::vec3;
+
+/// Permutation of 0 to 255 used for hashing the lattice points of the noise (the one of Ken
+/// Perlin's reference implementation)
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+/// Coordinate system a procedural texture is evaluated in
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureSpace {
+    /// Relative to the position of the object, so the pattern moves with the object
+    Object,
+    World,
+}
+
+/// Pattern of a procedural texture. Every pattern maps a point to a value between 0 and 1.
+#[derive(Debug, Copy, Clone)]
+pub enum Pattern {
+    /// Perlin noise
+    Noise,
+    /// Sum of `octaves` layers of noise, each with twice the frequency and half the amplitude
+    /// of the previous one
+    Turbulence { octaves: u32 },
+    /// Veins along the x-axis, distorted by turbulence
+    Marble { octaves: u32, distortion: f32 },
+    /// Rings around the y-axis, distorted by noise
+    Wood { distortion: f32 },
+    /// Cubes of size 1 with alternating values
+    Checker,
+    /// Layers of thickness 1 perpendicular to `direction` (normalized by
+    /// `ProceduralTexture::new`) with alternating values
+    Stripes { direction: vec3 },
+}
+
+/// Texture computed from the position of the hit point instead of an image
+#[derive(Debug, Copy, Clone)]
+pub struct ProceduralTexture {
+    pub pattern: Pattern,
+    /// Colors for the values 0 and 1 of the pattern, values in between are blended
+    pub colors: (vec3, vec3),
+    /// Frequency of the pattern, positions are multiplied with it before the pattern is evaluated
+    pub scale: f32,
+    pub space: TextureSpace,
+}
+
+/// Quintic interpolation curve with vanishing first and second derivative at 0 and 1
+fn fade(t: f32) -> f32 {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn hash(i: i64) -> i64 {
+    return PERMUTATION[i.rem_euclid(256) as usize] as i64;
+}
+
+/// Dot product of the offset (`x`, `y`, `z`) with one of twelve gradient directions chosen by
+/// `hash`
+fn gradient(hash: i64, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    return u + v;
+}
+
+/// Improved Perlin noise ("Improving Noise", 2002): smooth pseudo-random values between about
+/// -1 and 1, that vary on a scale of 1
+pub fn noise(p: vec3) -> f32 {
+    let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (x, y, z) = (p.x - x0, p.y - y0, p.z - z0);
+    let (xi, yi, zi) = (x0 as i64, y0 as i64, z0 as i64);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let corner = |dx: i64, dy: i64, dz: i64| {
+        let h = hash(hash(hash(xi + dx) + yi + dy) + zi + dz);
+        gradient(h, x - dx as f32, y - dy as f32, z - dz as f32)
+    };
+    let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+    return lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+        ),
+        lerp(
+            v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+        ),
+    );
+}
+
+/// Sum of the absolute values of `octaves` layers of noise with doubling frequency and halving
+/// amplitude
+pub fn turbulence(p: vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut weight = 1.0;
+    let mut p = p;
+    for _ in 0..octaves {
+        sum += weight * noise(p).abs();
+        weight *= 0.5;
+        p = p * 2.0;
+    }
+    return sum;
+}
+
+impl Pattern {
+    /// Value of the pattern at `p`, between 0 and 1
+    pub fn value(&self, p: vec3) -> f32 {
+        let value = match *self {
+            Pattern::Noise => 0.5 * (noise(p) + 1.0),
+            Pattern::Turbulence { octaves } => turbulence(p, octaves),
+            Pattern::Marble {
+                octaves,
+                distortion,
+            } => 0.5 * (1.0 + f32::sin(p.x + distortion * turbulence(p, octaves))),
+            Pattern::Wood { distortion } => {
+                let radius = f32::sqrt(p.x * p.x + p.z * p.z) + distortion * noise(p * 0.5);
+                radius - radius.floor()
+            }
+            Pattern::Checker => (p.x.floor() + p.y.floor() + p.z.floor()).rem_euclid(2.0),
+            Pattern::Stripes { direction } => (p * direction).floor().rem_euclid(2.0),
+        };
+        return value.clamp(0.0, 1.0);
+    }
+}
+
+impl ProceduralTexture {
+    /// Pattern blending from black to white with a scale of 1, evaluated in object space
+    pub fn new(pattern: Pattern) -> ProceduralTexture {
+        let pattern = match pattern {
+            Pattern::Stripes { mut direction } => Pattern::Stripes {
+                direction: direction.normalize(),
+            },
+            _ => pattern,
+        };
+        ProceduralTexture {
+            pattern: pattern,
+            colors: (
+                vec3 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                vec3 {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 1.0,
+                },
+            ),
+            scale: 1.0,
+            space: TextureSpace::Object,
+        }
+    }
+
+    pub fn with_colors(mut self, colors: (vec3, vec3)) -> ProceduralTexture {
+        self.colors = colors;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> ProceduralTexture {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_space(mut self, space: TextureSpace) -> ProceduralTexture {
+        self.space = space;
+        self
+    }
+
+    /// Color at a hit point (see `Texture::color`)
+    pub fn color(&self, point: vec3, object_point: vec3) -> vec3 {
+        let p = match self.space {
+            TextureSpace::Object => object_point,
+            TextureSpace::World => point,
+        };
+        let t = self.pattern.value(p * self.scale);
+        return self.colors.0 + (self.colors.1 - self.colors.0) * t;
+    }
+}
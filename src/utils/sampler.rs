@@ -0,0 +1,264 @@
+use rand::rngs::SmallRng;
+use rand::Rng;
+
+/// Distribution of the sample positions inside a pixel
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SamplePattern {
+    /// Regular grid, identical for every pixel
+    Grid,
+    /// One uniformly distributed sample inside each cell of a grid (stratified sampling)
+    Jittered,
+    /// Uniformly distributed samples
+    Random,
+    /// Halton sequence (bases 2 and 3), randomly shifted per pixel
+    Halton,
+    /// Sobol sequence (first two dimensions), randomly shifted per pixel
+    Sobol,
+    /// Regular grid rotated by `atan(1 / n)`, so no two samples share a row or a column
+    RotatedGrid,
+}
+
+/// Settings of adaptive sampling: every pixel gets at least `min_samples` samples, further
+/// samples are only added while the standard error of the mean luminance is above `threshold`
+#[derive(Debug, Copy, Clone)]
+pub struct AdaptiveSampling {
+    pub min_samples: u32,
+    pub threshold: f32,
+}
+
+impl AdaptiveSampling {
+    pub const fn new(min_samples: u32, threshold: f32) -> AdaptiveSampling {
+        AdaptiveSampling {
+            min_samples: min_samples,
+            threshold: threshold,
+        }
+    }
+}
+
+/// Generates the positions of the samples inside a pixel
+pub struct Sampler {
+    pattern: SamplePattern,
+    samples: u32,
+    /// Positions shared by all pixels (grid patterns) or base points of the sequences
+    base_points: Vec<(f32, f32)>,
+}
+
+/// Number of columns and rows of the smallest grid with at least `samples` cells
+fn grid_size(samples: u32) -> (u32, u32) {
+    let columns = f32::ceil(f32::sqrt(samples as f32)) as u32;
+    let rows = samples.div_ceil(columns);
+    return (columns, rows);
+}
+
+/// Van der Corput radical inverse of `i` in the given base
+fn radical_inverse(mut i: u32, base: u32) -> f32 {
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut result = 0.0;
+    while i > 0 {
+        result += (i % base) as f64 * factor;
+        i /= base;
+        factor *= inv_base;
+    }
+    return result as f32;
+}
+
+/// Second dimension of the Sobol sequence (the first one is the radical inverse in base 2)
+fn sobol_second_dimension(mut i: u32) -> f32 {
+    let mut v: u32 = 1 << 31;
+    let mut result: u32 = 0;
+    while i > 0 {
+        if i & 1 == 1 {
+            result ^= v;
+        }
+        i >>= 1;
+        v ^= v >> 1;
+    }
+    return result as f32 / 4294967296.0;
+}
+
+impl Sampler {
+    pub fn new(pattern: SamplePattern, samples: u32) -> Sampler {
+        let (columns, rows) = grid_size(samples);
+        let mut base_points: Vec<(f32, f32)> = Vec::new();
+        match pattern {
+            SamplePattern::Grid => {
+                // if the number of samples is not a square number, the samples are spread
+                // evenly over the rows, so some rows get one sample less than others
+                for i in 1..rows + 1 {
+                    let row_samples = samples * i / rows - samples * (i - 1) / rows;
+                    for j in 1..row_samples + 1 {
+                        base_points.push((
+                            f32::round(100.0 * (j as f32 / (row_samples + 1) as f32)) / 100.0,
+                            f32::round(100.0 * (i as f32 / (rows + 1) as f32)) / 100.0,
+                        ));
+                    }
+                }
+            }
+            SamplePattern::RotatedGrid => {
+                // the lattice spanned by (n, -1) and (1, n) is a square grid rotated by
+                // atan(1 / n) with exactly one point in every row and column of an n^2 x n^2 grid.
+                // If the number of samples is not a square number, evenly spaced columns are used.
+                let n = columns;
+                let cells = (n * n) as f32;
+                for k in 0..samples {
+                    let x = k * n * n / samples;
+                    let (i, j) = (x / n, x % n);
+                    let y = (j * n + n * n - i) % (n * n);
+                    base_points.push(((x as f32 + 0.5) / cells, (y as f32 + 0.5) / cells));
+                }
+            }
+            SamplePattern::Halton => {
+                for i in 0..samples {
+                    base_points.push((radical_inverse(i, 2), radical_inverse(i, 3)));
+                }
+            }
+            SamplePattern::Sobol => {
+                for i in 0..samples {
+                    base_points.push((radical_inverse(i, 2), sobol_second_dimension(i)));
+                }
+            }
+            SamplePattern::Jittered | SamplePattern::Random => {}
+        }
+        base_points.truncate(samples as usize);
+        return Sampler {
+            pattern: pattern,
+            samples: samples,
+            base_points: base_points,
+        };
+    }
+
+    pub fn samples(&self) -> u32 {
+        return self.samples;
+    }
+
+    /// `true` if every prefix of the samples of a pixel is well distributed
+    pub fn is_progressive(&self) -> bool {
+        return matches!(
+            self.pattern,
+            SamplePattern::Random | SamplePattern::Halton | SamplePattern::Sobol
+        );
+    }
+
+    /// Positions of the samples of one pixel, both coordinates in [0, 1). `rng` has to be
+    /// seeded per pixel to get reproducible images.
+    pub fn pixel_samples(&self, rng: &mut SmallRng) -> Vec<(f32, f32)> {
+        return match self.pattern {
+            SamplePattern::Grid | SamplePattern::RotatedGrid => self.base_points.clone(),
+            SamplePattern::Random => (0..self.samples).map(|_| (rng.gen(), rng.gen())).collect(),
+            SamplePattern::Jittered => {
+                let (columns, rows) = grid_size(self.samples);
+                let mut cells: Vec<u32> = (0..columns * rows).collect();
+                // if the grid has more cells than samples, a random subset of cells is used
+                for i in 0..self.samples as usize {
+                    let j = rng.gen_range(i..cells.len());
+                    cells.swap(i, j);
+                }
+                cells[..self.samples as usize].sort_unstable();
+                cells[..self.samples as usize]
+                    .iter()
+                    .map(|cell| {
+                        (
+                            ((cell % columns) as f32 + rng.gen::<f32>()) / columns as f32,
+                            ((cell / columns) as f32 + rng.gen::<f32>()) / rows as f32,
+                        )
+                    })
+                    .collect()
+            }
+            SamplePattern::Halton | SamplePattern::Sobol => {
+                // Cranley-Patterson rotation, so that neighbouring pixels do not share the
+                // same pattern
+                let shift: (f32, f32) = (rng.gen(), rng.gen());
+                self.base_points
+                    .iter()
+                    .map(|(x, y)| ((x + shift.0).fract(), (y + shift.1).fract()))
+                    .collect()
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const PATTERNS: [SamplePattern; 6] = [
+        SamplePattern::Grid,
+        SamplePattern::Jittered,
+        SamplePattern::Random,
+        SamplePattern::Halton,
+        SamplePattern::Sobol,
+        SamplePattern::RotatedGrid,
+    ];
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn samples_stay_inside_the_pixel() {
+        let mut rng = SmallRng::seed_from_u64(11);
+        for pattern in PATTERNS {
+            for samples in 1..=20 {
+                let sampler = Sampler::new(pattern, samples);
+                for _ in 0..50 {
+                    let points = sampler.pixel_samples(&mut rng);
+                    assert_eq!(points.len(), samples as usize, "{:?}", pattern);
+                    for (x, y) in points {
+                        assert!((0.0..1.0).contains(&x), "{:?}: x = {}", pattern, x);
+                        assert!((0.0..1.0).contains(&y), "{:?}: y = {}", pattern, y);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn radical_inverse_values() {
+        let base_2 = [0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875];
+        for (i, expected) in base_2.iter().enumerate() {
+            assert_close(radical_inverse(i as u32, 2), *expected);
+        }
+        let base_3 = [0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0, 7.0 / 9.0];
+        for (i, expected) in base_3.iter().enumerate() {
+            assert_close(radical_inverse(i as u32, 3), *expected);
+        }
+    }
+
+    #[test]
+    fn sobol_second_dimension_values() {
+        let expected = [0.0, 0.5, 0.75, 0.25, 0.625, 0.125, 0.375, 0.875];
+        for (i, expected) in expected.iter().enumerate() {
+            assert_close(sobol_second_dimension(i as u32), *expected);
+        }
+    }
+
+    #[test]
+    fn halton_and_sobol_base_points() {
+        let halton = Sampler::new(SamplePattern::Halton, 4);
+        let expected = [
+            (0.0, 0.0),
+            (0.5, 1.0 / 3.0),
+            (0.25, 2.0 / 3.0),
+            (0.75, 1.0 / 9.0),
+        ];
+        for ((x, y), (ex, ey)) in halton.base_points.iter().zip(expected) {
+            assert_close(*x, ex);
+            assert_close(*y, ey);
+        }
+
+        let sobol = Sampler::new(SamplePattern::Sobol, 4);
+        let expected = [(0.0, 0.0), (0.5, 0.5), (0.25, 0.75), (0.75, 0.25)];
+        for ((x, y), (ex, ey)) in sobol.base_points.iter().zip(expected) {
+            assert_close(*x, ex);
+            assert_close(*y, ey);
+        }
+    }
+}
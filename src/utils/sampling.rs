@@ -0,0 +1,52 @@
+use crate::utils::vec3::vec3;
+
+/// Two unit vectors that form an orthonormal basis together with `normal`
+pub fn orthonormal_basis(normal: vec3) -> (vec3, vec3) {
+    let helper = if normal.x.abs() > 0.9 {
+        vec3 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        }
+    } else {
+        vec3 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    };
+    let tangent = normal.cross(helper).normalize();
+    let bitangent = normal.cross(tangent);
+    return (tangent, bitangent);
+}
+
+/// Maps two uniformly distributed numbers in [0, 1) to a direction in the hemisphere around
+/// `normal`. The directions are distributed proportional to the cosine of the angle to the
+/// normal (pdf = cos(theta) / pi).
+pub fn cosine_weighted_hemisphere(normal: vec3, u1: f32, u2: f32) -> vec3 {
+    let r = f32::sqrt(u1);
+    let phi = 2.0 * std::f32::consts::PI * u2;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    return (tangent * (r * f32::cos(phi))
+        + bitangent * (r * f32::sin(phi))
+        + normal * f32::sqrt(f32::max(0.0, 1.0 - u1)))
+    .normalize();
+}
+
+/// Maps two uniformly distributed numbers in [0, 1) to a uniformly distributed point on the unit
+/// disk (concentric mapping by Shirley and Chiu, which keeps neighbouring samples together)
+pub fn concentric_disk(u1: f32, u2: f32) -> (f32, f32) {
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, phi) = if a.abs() > b.abs() {
+        (a, std::f32::consts::FRAC_PI_4 * (b / a))
+    } else {
+        (
+            b,
+            std::f32::consts::FRAC_PI_2 - std::f32::consts::FRAC_PI_4 * (a / b),
+        )
+    };
+    return (r * f32::cos(phi), r * f32::sin(phi));
+}
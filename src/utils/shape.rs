@@ -0,0 +1,25 @@
+use crate::utils::bvh::Aabb;
+use crate::utils::material::Material;
+use crate::utils::vec3::vec3;
+
+/// Intersection of a ray with a shape
+#[derive(Debug, Copy, Clone)]
+pub struct Hit<'a> {
+    pub distance: f32,
+    pub normal: vec3,
+    pub material: &'a Material,
+    /// Color replacing the one of the material, e.g. for the tiles of a checkerboard
+    pub color: Option<vec3>,
+    pub uv: (f32, f32),
+    /// Hit point relative to the position of the object
+    pub object_point: vec3,
+}
+
+/// Geometry that can be intersected by rays. Every object in the scene implements this
+/// trait, so new primitives only need to provide an intersection test and a bounding box.
+pub trait Shape: Send + Sync {
+    /// Nearest intersection of the ray with a distance greater than `min_dist`
+    fn intersect(&self, origin: vec3, direction: vec3, min_dist: f32) -> Option<Hit<'_>>;
+
+    fn bounding_box(&self) -> Aabb;
+}
@@ -0,0 +1,158 @@
+use crate::utils::vec3::vec3;
+
+/// Factor converting the luminance of the sky model (in kcd/m², about 10 at the zenith on a
+/// clear day) to the radiance scale of the scene, where a light intensity of 1 is bright
+/// daylight
+const LUMINANCE_SCALE: f32 = 0.05;
+
+/// Fraction of the radiance at the horizon returned for directions below it (the ground)
+const GROUND_ALBEDO: f32 = 0.3;
+
+/// Analytic daylight sky by Preetham, Shirley and Smits ("A Practical Analytic Model for
+/// Daylight", 1999). The luminance and chromaticity of every direction are given by the Perez
+/// formula relative to the zenith, with coefficients depending on the turbidity (haziness) of
+/// the atmosphere.
+#[derive(Debug, Copy, Clone)]
+pub struct Sky {
+    /// Unit vector pointing to the sun
+    pub sun_direction: vec3,
+    pub turbidity: f32,
+    /// Factor the radiance of the sky is multiplied with
+    pub intensity: f32,
+    /// Perez coefficients A to E of the luminance Y and the chromaticities x and y
+    perez: [[f32; 5]; 3],
+    /// Y, x and y at the zenith divided by the Perez function at the zenith
+    zenith: [f32; 3],
+}
+
+/// Direction of the sun for the elevation above the horizon and the azimuth (both in degrees),
+/// the azimuth is measured from -z towards +x
+pub fn sun_direction(elevation: f32, azimuth: f32) -> vec3 {
+    let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+    return vec3 {
+        x: f32::cos(elevation) * f32::sin(azimuth),
+        y: f32::sin(elevation),
+        z: -f32::cos(elevation) * f32::cos(azimuth),
+    };
+}
+
+/// Perez sky distribution for the angle `theta` between the direction and the zenith and the
+/// angle `gamma` between the direction and the sun
+fn perez_function(coefficients: &[f32; 5], theta: f32, gamma: f32) -> f32 {
+    let [a, b, c, d, e] = *coefficients;
+    return (1.0 + a * f32::exp(b / f32::cos(theta)))
+        * (1.0 + c * f32::exp(d * gamma) + e * f32::cos(gamma) * f32::cos(gamma));
+}
+
+/// Converts the luminance `big_y` and the chromaticity (`x`, `y`) to linear sRGB
+fn yxy_to_rgb(big_y: f32, x: f32, y: f32) -> vec3 {
+    let big_x = x * big_y / y;
+    let big_z = (1.0 - x - y) * big_y / y;
+    return vec3 {
+        x: 3.2406 * big_x - 1.5372 * big_y - 0.4986 * big_z,
+        y: -0.9689 * big_x + 1.8758 * big_y + 0.0415 * big_z,
+        z: 0.0557 * big_x - 0.2040 * big_y + 1.0570 * big_z,
+    };
+}
+
+impl Sky {
+    pub fn new(mut sun_direction: vec3, turbidity: f32, intensity: f32) -> Sky {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity;
+        // the model is only defined for the sun above the horizon
+        let theta_s = f32::min(
+            f32::acos(sun_direction.y),
+            0.999 * std::f32::consts::FRAC_PI_2,
+        );
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f32::consts::PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * f32::tan(chi) - 0.2155 * t + 2.4192;
+        let (s1, s2, s3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s1)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s1 + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s1 + 0.25886);
+        let zenith_y = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s1)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s1 + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s1 + 0.26688);
+
+        let zenith = [
+            zenith_luminance / perez_function(&perez[0], 0.0, theta_s),
+            zenith_x / perez_function(&perez[1], 0.0, theta_s),
+            zenith_y / perez_function(&perez[2], 0.0, theta_s),
+        ];
+        return Sky {
+            sun_direction: sun_direction,
+            turbidity: turbidity,
+            intensity: intensity,
+            perez: perez,
+            zenith: zenith,
+        };
+    }
+
+    /// Radiance (linear RGB) of the sky in the (normalized) `direction`
+    pub fn radiance(&self, direction: vec3) -> vec3 {
+        // directions at or below the horizon use the color of the horizon
+        let theta = f32::acos(direction.y.clamp(0.001, 1.0));
+        let gamma = f32::acos((direction * self.sun_direction).clamp(-1.0, 1.0));
+        let big_y = self.zenith[0] * perez_function(&self.perez[0], theta, gamma);
+        let x = self.zenith[1] * perez_function(&self.perez[1], theta, gamma);
+        let y = self.zenith[2] * perez_function(&self.perez[2], theta, gamma);
+
+        let mut color = yxy_to_rgb(big_y, x, y) * (LUMINANCE_SCALE * self.intensity);
+        if direction.y < 0.0 {
+            color = color * GROUND_ALBEDO;
+        }
+        return vec3 {
+            x: f32::max(color.x, 0.0),
+            y: f32::max(color.y, 0.0),
+            z: f32::max(color.z, 0.0),
+        };
+    }
+
+    /// Fraction of the sunlight (per RGB channel) that passes through the atmosphere, from the
+    /// optical depth of Rayleigh scattering and of the aerosols (Ångström's formula with the
+    /// turbidity dependent coefficient of Preetham et al.) along the path of the light
+    pub fn sun_transmittance(&self) -> vec3 {
+        let elevation = f32::max(f32::asin(self.sun_direction.y).to_degrees(), 0.0);
+        // relative optical air mass (Kasten and Young)
+        let air_mass = 1.0
+            / (f32::sin(elevation.to_radians())
+                + 0.50572 * f32::powf(elevation + 6.07995, -1.6364));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        // wavelengths of red, green and blue in micrometers
+        let transmittance = |wavelength: f32| {
+            let rayleigh = 0.008735 * f32::powf(wavelength, -4.08);
+            let aerosol = beta * f32::powf(wavelength, -1.3);
+            f32::exp(-air_mass * (rayleigh + aerosol))
+        };
+        return vec3 {
+            x: transmittance(0.680),
+            y: transmittance(0.550),
+            z: transmittance(0.440),
+        };
+    }
+}
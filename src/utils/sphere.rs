@@ -1,7 +1,9 @@
-use crate::utils::vec3::vec3;
+use crate::utils::bvh::Aabb;
 use crate::utils::material::Material;
+use crate::utils::shape::{Hit, Shape};
+use crate::utils::vec3::vec3;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct Sphere {
     pub center: vec3,
     pub radius: f32,
@@ -16,4 +18,51 @@ impl Sphere {
             material: material,
         }
     }
-}
\ No newline at end of file
+
+}
+
+impl Shape for Sphere {
+    fn intersect(&self, origin: vec3, direction: vec3, min_dist: f32) -> Option<Hit<'_>> {
+        let vec_to_center = self.center - origin;
+        let projection_of_dir_to_center = vec_to_center * direction;
+        let discriminant =
+            vec_to_center * vec_to_center - f32::powf(projection_of_dir_to_center, 2.0);
+        if discriminant >= f32::powf(self.radius, 2.0) {
+            return None;
+        }
+        let tmp = f32::sqrt(f32::powf(self.radius, 2.0) - discriminant);
+        let t0 = projection_of_dir_to_center - tmp;
+        let t1 = projection_of_dir_to_center + tmp;
+        let distance = if t0 > min_dist {
+            t0
+        } else if t1 > min_dist {
+            t1
+        } else {
+            return None;
+        };
+        let mut object_point = origin + direction * distance - self.center;
+        let normal = object_point.normalize();
+        return Some(Hit {
+            distance: distance,
+            normal: normal,
+            material: &self.material,
+            color: None,
+            // u runs around the y-axis with the center of the texture facing +z, v from the top
+            // to the bottom
+            uv: (
+                0.5 + f32::atan2(normal.x, normal.z) / (2.0 * std::f32::consts::PI),
+                0.5 - f32::asin(normal.y) / std::f32::consts::PI,
+            ),
+            object_point: object_point,
+        });
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = vec3 {
+            x: self.radius,
+            y: self.radius,
+            z: self.radius,
+        };
+        Aabb::new(self.center - r, self.center + r)
+    }
+}
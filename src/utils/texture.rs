@@ -0,0 +1,143 @@
+use crate::utils::procedural::ProceduralTexture;
+use crate::utils::tonemap::srgb_to_linear;
+use crate::utils::vec3::vec3;
+
+/// Handling of texture coordinates outside of [0, 1]
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureWrap {
+    /// The image is repeated
+    Wrap,
+    /// The pixels at the edges of the image are extended
+    Clamp,
+}
+
+/// Interpolation between the pixels of a texture
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+}
+
+/// Source of a color (or, by its luminance, a multiplier) that varies over a surface
+#[derive(Debug)]
+pub enum Texture {
+    Image(ImageTexture),
+    Procedural(ProceduralTexture),
+}
+
+impl Texture {
+    /// Value at a hit point. `uv` are the texture coordinates of the hit, `point` its position
+    /// in world coordinates and `object_point` its position relative to the object (see
+    /// `Hit::object_point`). Image textures use `uv`, procedural textures one of the points
+    /// depending on their `TextureSpace`.
+    pub fn color(&self, uv: (f32, f32), point: vec3, object_point: vec3) -> vec3 {
+        return match self {
+            Texture::Image(texture) => texture.color(uv),
+            Texture::Procedural(texture) => texture.color(point, object_point),
+        };
+    }
+}
+
+/// Image mapped onto a surface by the texture coordinates (u, v) of the hit point. (0, 0) is
+/// the upper left corner of the image, (1, 1) the lower right corner.
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<vec3>,
+    pub wrap: TextureWrap,
+    pub filter: TextureFilter,
+    /// Number of repetitions of the image along u and v
+    pub scale: (f32, f32),
+}
+
+impl std::fmt::Debug for ImageTexture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageTexture")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("wrap", &self.wrap)
+            .field("filter", &self.filter)
+            .field("scale", &self.scale)
+            .finish()
+    }
+}
+
+impl ImageTexture {
+    /// Loads an image. With `linearize` the sRGB encoded colors are converted to linear values,
+    /// which is correct for colors; textures holding other values (e.g. the strength of
+    /// reflections) are used as stored.
+    pub fn load(
+        path: &std::path::Path,
+        linearize: bool,
+    ) -> Result<ImageTexture, image::ImageError> {
+        let img = image::open(path)?.into_rgb32f();
+        let convert = |c: f32| if linearize { srgb_to_linear(c) } else { c };
+        let pixels = img
+            .pixels()
+            .map(|p| vec3 {
+                x: convert(p.0[0]),
+                y: convert(p.0[1]),
+                z: convert(p.0[2]),
+            })
+            .collect();
+        return Ok(ImageTexture {
+            width: img.width(),
+            height: img.height(),
+            pixels: pixels,
+            wrap: TextureWrap::Wrap,
+            filter: TextureFilter::Bilinear,
+            scale: (1.0, 1.0),
+        });
+    }
+
+    pub fn with_wrap(mut self, wrap: TextureWrap) -> ImageTexture {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: TextureFilter) -> ImageTexture {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: (f32, f32)) -> ImageTexture {
+        self.scale = scale;
+        self
+    }
+
+    /// Index of the pixel `i` (which may lie outside of the image) along an axis with `size`
+    /// pixels
+    fn wrap_index(&self, i: i64, size: u32) -> u32 {
+        return match self.wrap {
+            TextureWrap::Wrap => i.rem_euclid(size as i64) as u32,
+            TextureWrap::Clamp => i.clamp(0, size as i64 - 1) as u32,
+        };
+    }
+
+    fn pixel(&self, column: i64, row: i64) -> vec3 {
+        let column = self.wrap_index(column, self.width);
+        let row = self.wrap_index(row, self.height);
+        return self.pixels[(row * self.width + column) as usize];
+    }
+
+    /// Color at the texture coordinates `uv`
+    pub fn color(&self, uv: (f32, f32)) -> vec3 {
+        // position in pixels, the centers of the pixels lie at half-integer positions
+        let x = uv.0 * self.scale.0 * self.width as f32;
+        let y = uv.1 * self.scale.1 * self.height as f32;
+        return match self.filter {
+            TextureFilter::Nearest => self.pixel(x.floor() as i64, y.floor() as i64),
+            TextureFilter::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (column, row) = (x.floor() as i64, y.floor() as i64);
+                let (tx, ty) = (x - x.floor(), y - y.floor());
+                let top = self.pixel(column, row) * (1.0 - tx) + self.pixel(column + 1, row) * tx;
+                let bottom =
+                    self.pixel(column, row + 1) * (1.0 - tx) + self.pixel(column + 1, row + 1) * tx;
+                top * (1.0 - ty) + bottom * ty
+            }
+        };
+    }
+}
@@ -0,0 +1,80 @@
+use crate::utils::vec3::vec3;
+
+/// Operator compressing the unbounded linear radiance into the displayable range 0.0 to 1.0
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneMapping {
+    /// Clip every channel at 1.0
+    Clamp,
+    /// `c / (1 + c)` per channel
+    Reinhard,
+    /// Filmic curve of the ACES reference rendering transform (fit by Krzysztof Narkowicz)
+    Aces,
+}
+
+/// Transfer function used for encoding the tone mapped colors
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gamma {
+    /// Store the linear values
+    Linear,
+    /// sRGB transfer function (roughly a gamma of 2.2)
+    Srgb,
+}
+
+/// Post-processing stage between the linear colors of the framebuffer and 8 bit images
+#[derive(Debug, Copy, Clone)]
+pub struct ToneMapper {
+    pub operator: ToneMapping,
+    /// Exposure correction in stops, the colors are multiplied by `2^exposure`
+    pub exposure: f32,
+    pub gamma: Gamma,
+}
+
+impl ToneMapper {
+    pub const fn new(operator: ToneMapping, exposure: f32, gamma: Gamma) -> ToneMapper {
+        ToneMapper {
+            operator: operator,
+            exposure: exposure,
+            gamma: gamma,
+        }
+    }
+
+    fn map_channel(&self, c: f32) -> f32 {
+        let c = f32::max(c, 0.0);
+        let mapped = match self.operator {
+            ToneMapping::Clamp => c,
+            ToneMapping::Reinhard => c / (1.0 + c),
+            ToneMapping::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        };
+        let mapped = f32::clamp(mapped, 0.0, 1.0);
+        return match self.gamma {
+            Gamma::Linear => mapped,
+            Gamma::Srgb => {
+                if mapped <= 0.0031308 {
+                    12.92 * mapped
+                } else {
+                    1.055 * f32::powf(mapped, 1.0 / 2.4) - 0.055
+                }
+            }
+        };
+    }
+
+    /// Maps a linear color to the range 0.0 to 1.0
+    pub fn apply(&self, color: vec3) -> vec3 {
+        let color = color * f32::powf(2.0, self.exposure);
+        return vec3 {
+            x: self.map_channel(color.x),
+            y: self.map_channel(color.y),
+            z: self.map_channel(color.z),
+        };
+    }
+}
+
+/// Converts an sRGB encoded value to linear (the inverse of `Gamma::Srgb`)
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        return c / 12.92;
+    }
+    return f32::powf((c + 0.055) / 1.055, 2.4);
+}
@@ -0,0 +1,94 @@
+use crate::utils::bvh::Aabb;
+use crate::utils::material::Material;
+use crate::utils::shape::{Hit, Shape};
+use crate::utils::vec3::vec3;
+
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    pub vertices: [vec3; 3],
+    pub normals: Option<[vec3; 3]>,
+    /// Shared by all triangles of a mesh
+    pub material: Arc<Material>,
+}
+
+impl Triangle {
+    pub const fn new(
+        vertices: [vec3; 3],
+        normals: Option<[vec3; 3]>,
+        material: Arc<Material>,
+    ) -> Triangle {
+        Triangle {
+            vertices: vertices,
+            normals: normals,
+            material: material,
+        }
+    }
+
+    /// Möller–Trumbore intersection. Returns the distance and the barycentric coordinates
+    /// `(u, v)` of the hit point.
+    pub fn intersect_barycentric(
+        &self,
+        origin: vec3,
+        direction: vec3,
+        min_dist: f32,
+    ) -> Option<(f32, f32, f32)> {
+        let edge1 = self.vertices[1] - self.vertices[0];
+        let edge2 = self.vertices[2] - self.vertices[0];
+        let p = direction.cross(edge2);
+        let det = edge1 * p;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = origin - self.vertices[0];
+        let u = (s * p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let v = (direction * q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = (edge2 * q) * inv_det;
+        if t > min_dist {
+            return Some((t, u, v));
+        }
+        return None;
+    }
+
+    /// Normal at the barycentric coordinates `(u, v)`. Vertex normals are interpolated if
+    /// present (smooth shading), otherwise the face normal is used.
+    pub fn normal_at(&self, u: f32, v: f32) -> vec3 {
+        match self.normals {
+            Some([n0, n1, n2]) => (n0 * (1.0 - u - v) + n1 * u + n2 * v).normalize(),
+            None => (self.vertices[1] - self.vertices[0])
+                .cross(self.vertices[2] - self.vertices[0])
+                .normalize(),
+        }
+    }
+}
+
+impl Shape for Triangle {
+    fn intersect(&self, origin: vec3, direction: vec3, min_dist: f32) -> Option<Hit<'_>> {
+        let (distance, u, v) = self.intersect_barycentric(origin, direction, min_dist)?;
+        return Some(Hit {
+            distance: distance,
+            normal: self.normal_at(u, v),
+            material: self.material.as_ref(),
+            color: None,
+            uv: (u, v),
+            // triangles have no position of their own
+            object_point: origin + direction * distance,
+        });
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.vertices;
+        Aabb::new(a, a)
+            .union(Aabb::new(b, b))
+            .union(Aabb::new(c, c))
+    }
+}
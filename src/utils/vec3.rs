@@ -1,6 +1,5 @@
 #[derive(Debug, Copy, Clone)]
 #[allow(non_camel_case_types)]
-
 pub struct vec3 {
     pub x: f32,
     pub y: f32,
@@ -90,6 +89,24 @@ impl vec3 {
         return f32::sqrt(self.x * self.x + self.y * self.y + self.z * self.z);
     }
 
+    /// Component-wise product, e.g. for filtering a color by another color
+    pub fn mul_elementwise(self, vec: vec3) -> vec3 {
+        return vec3 {
+            x: self.x * vec.x,
+            y: self.y * vec.y,
+            z: self.z * vec.z,
+        };
+    }
+
+    pub fn max_component(self) -> f32 {
+        return f32::max(self.x, f32::max(self.y, self.z));
+    }
+
+    /// Relative luminance of a linear RGB color (Rec. 709 weights)
+    pub fn luminance(self) -> f32 {
+        return 0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z;
+    }
+
     pub fn cross(self, vec: vec3) -> vec3 {
         return vec3 {
             x: self.y * vec.z - self.z * vec.y,
@@ -107,37 +124,6 @@ impl vec3 {
         };
     }
 
-    pub fn look_at(from: vec3, to: vec3, vec: vec3) -> vec3 {
-        let forward = (from - to).normalize();
-        let right = vec3 {
-            x: 0.0,
-            y: 1.0,
-            z: 0.0,
-        }
-        .cross(forward);
-        let up = forward.cross(right);
-
-        let translation_x = -from * right;
-        let translation_y = -from * up;
-        let translation_z = -from * forward;
-
-        let transformation_matrix = ndarray::arr2(&[
-            [right.x, up.x, forward.x, 0.0],
-            [right.y, up.y, forward.y, 0.0],
-            [right.z, up.z, forward.z, 0.0],
-            [translation_x, translation_y, translation_z, 1.0],
-        ]);
-
-        let vec_ = ndarray::arr1(&[vec.x, vec.y, vec.z, 0.0]);
-        let new_vec = transformation_matrix.dot(&vec_);
-
-        return vec3 {
-            x: new_vec[0],
-            y: new_vec[1],
-            z: new_vec[2],
-        };
-    }
-
     #[allow(dead_code)]
     pub fn rot_x(&mut self, theta: f32) -> vec3 {
         return vec3 {